@@ -1,110 +1,8 @@
 use crate::rpolarserr::*;
 use extendr_api::prelude::*;
 use polars::prelude as pl;
-use polars_core::export::rayon::prelude::*;
 use polars_core::prelude::*;
-use polars_core::utils::accumulate_dataframes_vertical_unchecked;
 use polars_core::utils::arrow::ffi;
-use polars_core::POOL;
-use std::result::Result;
-
-use super::RArrowArrayClass;
-
-pub fn arrow_array_to_rust(arrow_array: Robj) -> Result<ArrayRef, String> {
-    let mut array = Box::new(ffi::ArrowArray::empty());
-    let mut schema = Box::new(ffi::ArrowSchema::empty());
-    let (ext_a, ext_s) = unsafe {
-        (
-            wrap_make_external_ptr(&mut *array),
-            wrap_make_external_ptr(&mut *schema),
-        )
-    };
-
-    RArrowArrayClass::from_robj(&arrow_array)?
-        .get_package()
-        .get_export_array_func()?
-        .call(pairlist!(&arrow_array, ext_a, ext_s))?;
-
-    let array = unsafe {
-        let field = ffi::import_field_from_c(schema.as_ref()).map_err(|err| err.to_string())?;
-        ffi::import_array_from_c(*array, field.data_type).map_err(|err| err.to_string())?
-    };
-    Ok(array)
-}
-
-unsafe fn wrap_make_external_ptr<T>(t: &mut T) -> Robj {
-    unsafe { <Integers>::make_external_ptr(t, r!(extendr_api::NULL)) }
-}
-
-pub fn to_rust_df(rb: Robj) -> Result<pl::DataFrame, String> {
-    let rb = rb.as_list().ok_or("arrow record batches is not a List")?;
-    let get_columns_f = R!(r"\(x) x$columns")?.as_function().unwrap();
-
-    //read columns names of first batch, if not any batches return empty DataFrame
-    let robj_record_batch_names = if let Ok(first_rb) = rb.elt(0) {
-        call!(r"\(x) x$schema$names", first_rb).map_err(|err| format!("internal error: {}", err))?
-    } else {
-        return Ok(pl::DataFrame::default());
-    };
-    let names = robj_record_batch_names
-        .as_str_vector()
-        .ok_or_else(|| "internal error: Robj$schema$names is not a char vec".to_string())?;
-
-    //iterate over record batches
-    let rb_len = rb.len();
-    let dfs_iter = rb.iter().map(|(_, rb)| {
-        //do not run parallel unless data.type matches ...
-        let mut run_parallel = false;
-
-        //get list of columns for this record batch
-        let columns_list = get_columns_f
-            .call(pairlist!(rb))?
-            .as_list()
-            .expect("arrow columns always wrapped in list");
-        let n_columns = columns_list.len();
-
-        //collect vector of exported arrow arrays, one for each column
-        let array_iter = columns_list.into_iter().map(|(_, column)| {
-            let arr = arrow_array_to_rust(column)?;
-            run_parallel |= matches!(
-                arr.data_type(),
-                ArrowDataType::Utf8 | ArrowDataType::Dictionary(_, _, _)
-            );
-            let list_res: Result<_, String> = Ok(arr);
-            list_res
-        });
-        let arrays_vec = crate::utils::collect_hinted_result(n_columns, array_iter)?;
-
-        // we parallelize this part because we can have dtypes that are not zero copy
-        // for instance utf8 -> large-utf8
-        // dict encoded to categorical
-
-        let series_vec = if run_parallel {
-            POOL.install(|| {
-                arrays_vec
-                    .into_par_iter()
-                    .zip(names.par_iter())
-                    .map(|(arr, name)| {
-                        let s = Series::try_from((*name, arr)).map_err(|err| err.to_string())?;
-                        Ok(s)
-                    })
-                    .collect::<Result<Vec<_>, String>>()
-            })
-        } else {
-            let iter = arrays_vec.into_iter().zip(names.iter()).map(|(arr, name)| {
-                let s = Series::try_from((*name, arr)).map_err(|err| err.to_string())?;
-                Ok(s)
-            });
-            crate::utils::collect_hinted_result(n_columns, iter)
-        }?;
-
-        // no need to check as a record batch has the same guarantees
-        let df_res: Result<_, String> = Ok(DataFrame::new_no_checks(series_vec));
-        df_res
-    });
-    let dfs = crate::utils::collect_hinted_result(rb_len, dfs_iter)?;
-    Ok(accumulate_dataframes_vertical_unchecked(dfs))
-}
 
 // r-polars as consumer 1: create a new stream and wrap pointer in Robj as str.
 pub fn new_arrow_stream_internal() -> Robj {
@@ -125,36 +23,51 @@ pub fn arrow_stream_to_series_internal(robj_str: Robj) -> RResult<pl::Series> {
     Ok(s)
 }
 
+// r-polars as consumer 4: the producer hands over an external pointer to its own stream, e.g.
+// a `nanoarrow_array_stream`. The stream is moved out of the pointer, which leaves the producer
+// with a released stream, and consumed without calling back into R.
+pub fn arrow_stream_ptr_to_series_internal(stream_ptr: Robj) -> RResult<pl::Series> {
+    if stream_ptr.rtype() != Rtype::ExternalPtr {
+        return rerr()
+            .plain("expected an external pointer to an arrow array stream")
+            .bad_robj(&stream_ptr)
+            .when("importing arrow array stream");
+    }
+    let ptr = unsafe { stream_ptr.external_ptr_addr::<ffi::ArrowArrayStream>() };
+    if ptr.is_null() {
+        return rerr()
+            .plain("the external pointer to the arrow array stream is null")
+            .when("importing arrow array stream");
+    }
+    let stream = unsafe { std::ptr::replace(ptr, ffi::ArrowArrayStream::empty()) };
+    consume_arrow_stream_to_series(Box::new(stream))
+}
+
 // implementation of consuming stream to Series. Stream is drop/released hereafter.
 fn consume_arrow_stream_to_series(boxed_stream: Box<ffi::ArrowArrayStream>) -> RResult<pl::Series> {
     let mut iter = unsafe { ffi::ArrowArrayStreamReader::try_new(boxed_stream) }?;
 
-    //import first array into pl::Series
+    //import first array into pl::Series, an empty stream gives an empty Series of the stream type
     let mut s = if let Some(array_res) = unsafe { iter.next() } {
-        let array = array_res?;
-        let series_res: pl::PolarsResult<pl::Series> =
-            std::convert::TryFrom::try_from(("df", array));
-
-        series_res.map_err(polars_to_rpolars_err)?
+        array_to_series(array_res?)?
     } else {
-        rerr()
-            .plain("Arrow array stream was empty")
-            .hint("producer did not export to stream")
-            .when("consuming arrow array stream")?;
-        unreachable!();
+        let dtype = pl::DataType::from(iter.field().data_type());
+        return Ok(pl::Series::new_empty("", &dtype));
     };
 
     // append any other arrays to Series
     while let Some(array_res) = unsafe { iter.next() } {
-        let array = array_res?;
-        let series_res: pl::PolarsResult<pl::Series> =
-            std::convert::TryFrom::try_from(("df", array));
-        let series = series_res.map_err(polars_to_rpolars_err)?;
+        let series = array_to_series(array_res?)?;
         s.append(&series).map_err(polars_to_rpolars_err)?;
     }
     Ok(s)
 }
 
+// dictionary arrays become Categorical, large lists List and struct arrays Struct Series.
+fn array_to_series(array: ArrayRef) -> RResult<pl::Series> {
+    pl::Series::try_from(("", array)).map_err(polars_to_rpolars_err)
+}
+
 pub unsafe fn export_df_as_stream(df: pl::DataFrame, robj_str_ref: &Robj) -> RResult<()> {
     let stream_ptr =
         crate::utils::robj_str_ptr_to_usize(robj_str_ref)? as *mut ffi::ArrowArrayStream;
@@ -165,3 +78,99 @@ pub unsafe fn export_df_as_stream(df: pl::DataFrame, robj_str_ref: &Robj) -> RRe
     unsafe { *stream_ptr = ffi::export_iterator(iter_boxed, field) };
     Ok(())
 }
+
+// The stream pointers of a C stream are only called from one thread at a time, as it is
+// consumed under a Mutex.
+struct SendStreamReader(ffi::ArrowArrayStreamReader<Box<ffi::ArrowArrayStream>>);
+unsafe impl Send for SendStreamReader {}
+
+// A scan source that consumes an arrow array stream of struct arrays. The filters, projections
+// and slices pushed down by the optimizer are applied to each record batch, so that only the
+// reduced result is held in memory. A stream can only be consumed once.
+struct ArrowStreamScan {
+    reader: std::sync::Mutex<Option<SendStreamReader>>,
+}
+
+impl pl::AnonymousScan for ArrowStreamScan {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn scan(&self, args: pl::AnonymousScanArgs) -> pl::PolarsResult<pl::DataFrame> {
+        let Some(SendStreamReader(mut reader)) = self.reader.lock().unwrap().take() else {
+            pl::polars_bail!(
+                ComputeError: "the arrow stream was already consumed, a LazyFrame scanning a stream can only be collected once"
+            );
+        };
+        let columns: Option<Vec<pl::Expr>> = args
+            .with_columns
+            .as_ref()
+            .map(|cols| cols.iter().map(|name| pl::col(name)).collect());
+
+        let mut dfs = Vec::new();
+        let mut height = 0;
+        while let Some(array) = unsafe { reader.next() } {
+            if args.n_rows.is_some_and(|n| height >= n) {
+                break;
+            }
+            let s = Series::try_from(("", array?))?;
+            let mut lf = pl::IntoLazy::lazy(pl::DataFrame::from(s.struct_()?.clone()));
+            if let Some(predicate) = &args.predicate {
+                lf = lf.filter(predicate.clone());
+            }
+            if let Some(columns) = &columns {
+                lf = lf.select(columns.clone());
+            }
+            let df = lf.collect()?;
+            height += df.height();
+            dfs.push(df);
+        }
+
+        let df = if dfs.is_empty() {
+            let schema = args.output_schema.unwrap_or(args.schema);
+            pl::DataFrame::from(schema.as_ref())
+        } else {
+            accumulate_dataframes_vertical_unchecked(dfs)
+        };
+        Ok(match args.n_rows {
+            Some(n) => df.slice(0, n),
+            None => df,
+        })
+    }
+
+    fn allows_predicate_pushdown(&self) -> bool {
+        true
+    }
+
+    fn allows_projection_pushdown(&self) -> bool {
+        true
+    }
+
+    fn allows_slice_pushdown(&self) -> bool {
+        true
+    }
+}
+
+// r-polars as consumer 3: like consumer 2, but the stream is wrapped in a LazyFrame and only
+// consumed when the LazyFrame is collected.
+pub fn arrow_stream_to_lazyframe_internal(robj_str: Robj) -> RResult<pl::LazyFrame> {
+    let us = crate::utils::robj_str_ptr_to_usize(&robj_str)?;
+    let boxed_stream = unsafe { Box::from_raw(us as *mut ffi::ArrowArrayStream) };
+    let reader = unsafe { ffi::ArrowArrayStreamReader::try_new(boxed_stream) }?;
+
+    let ArrowDataType::Struct(fields) = reader.field().data_type() else {
+        return rerr()
+            .plain("the arrow stream must contain struct arrays, i.e. record batches")
+            .when("scanning arrow array stream");
+    };
+    let schema = std::sync::Arc::new(pl::Schema::from_iter(fields.iter()));
+    let scan = ArrowStreamScan {
+        reader: std::sync::Mutex::new(Some(SendStreamReader(reader))),
+    };
+    let args = pl::ScanArgsAnonymous {
+        schema: Some(schema),
+        name: "ARROW STREAM",
+        ..Default::default()
+    };
+    pl::LazyFrame::anonymous_scan(std::sync::Arc::new(scan), args).map_err(polars_to_rpolars_err)
+}
@@ -60,7 +60,7 @@ fn recursive_robjname2series_tree(x: &Robj, name: &str) -> pl::PolarsResult<Seri
                 "This externalPtr is currently not supported".into(),
             )),
         },
-        Rtype::Doubles if x.inherits("integer64") => {
+        Rtype::Doubles if x.inherits("integer64") || x.inherits("nanotime") => {
             let rdouble: Doubles = x.try_into().expect("as matched");
             if rdouble.no_na().is_true() {
                 let real_slice = x.as_real_slice().unwrap();
@@ -114,16 +114,47 @@ fn recursive_robjname2series_tree(x: &Robj, name: &str) -> pl::PolarsResult<Seri
             Ok(SeriesTree::Series(pl::Series::new(name, s)))
         }
 
-        Rtype::Integers if x.inherits("factor") => Ok(SeriesTree::Series(
-            robj_to_utf8_series(
-                x.as_character_factor()
-                    .try_into()
-                    .expect("as_character_factor() enforces same type"),
-                name,
-            )
-            .cast(&pl::DataType::Categorical(None, Default::default()))
-            .expect("as matched"),
-        )),
+        // ordered factors have a fixed set of ordered levels, like an Enum
+        Rtype::Integers if x.inherits("ordered") => {
+            let levels = x
+                .get_attrib("levels")
+                .unwrap_or_else(|| r!(extendr_api::NULL));
+            let levels = levels.as_str_iter().into_iter().flatten();
+            let dtype = crate::rdatatype::new_enum_datatype(levels)?;
+            Ok(SeriesTree::Series(
+                robj_to_utf8_series(
+                    x.as_character_factor()
+                        .try_into()
+                        .expect("as_character_factor() enforces same type"),
+                    name,
+                )
+                .cast(&dtype)?,
+            ))
+        }
+
+        // the levels are placed first so that the categories follow their order, including
+        // unused levels, and are sliced off afterwards
+        Rtype::Integers if x.inherits("factor") => {
+            let levels = x
+                .get_attrib("levels")
+                .unwrap_or_else(|| r!(extendr_api::NULL));
+            let levels: Vec<&str> = levels.as_str_iter().into_iter().flatten().collect();
+            let codes = x.as_integers().expect("as matched");
+            let mut strings: Vec<Option<&str>> = Vec::with_capacity(levels.len() + codes.len());
+            strings.extend(levels.iter().map(|level| Some(*level)));
+            strings.extend(codes.iter().map(|code| {
+                if code.is_na() {
+                    None
+                } else {
+                    levels.get(code.inner() as usize - 1).copied()
+                }
+            }));
+            let s = pl::Series::new(name, strings)
+                .cast(&pl::DataType::Categorical(None, Default::default()))?;
+            Ok(SeriesTree::Series(
+                s.slice(levels.len() as i64, codes.len()),
+            ))
+        }
 
         Rtype::Integers => {
             let rints = x.as_integers().expect("as matched");
@@ -171,6 +202,19 @@ fn recursive_robjname2series_tree(x: &Robj, name: &str) -> pl::PolarsResult<Seri
             Ok(SeriesTree::Series(binary_series))
         }
 
+        // a data.frame, e.g. nested in a list column, is a Struct with a field per column
+        Rtype::List if x.inherits("data.frame") => {
+            let fields: Vec<pl::Series> = collect_hinted_result(
+                x.len(),
+                x.as_list()
+                    .unwrap()
+                    .iter()
+                    .map(|(name, robj)| robjname2series(robj, name)),
+            )?;
+            let s = pl::StructChunked::new(name, &fields)?.into_series();
+            Ok(SeriesTree::Series(s))
+        }
+
         Rtype::List => {
             // Recusively handle elements of list
             let result_series_vec: pl::PolarsResult<Vec<SeriesTree>> = collect_hinted_result(
@@ -209,14 +253,73 @@ fn recursive_robjname2series_tree(x: &Robj, name: &str) -> pl::PolarsResult<Seri
                     })
                 })
                 .flatten();
-            //todo this could probably in fewer allocations
-            let dt = pl::DataType::Datetime(pl::TimeUnit::Milliseconds, tz);
+            // POSIXct is seconds since epoch as double, keep it in microseconds to not lose
+            // sub-millisecond precision
+            let mut us: pl::Int64Chunked = s
+                .cast(&pl::DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|opt| opt.map(|val| (val * 1_000_000.0).round() as i64))
+                .collect();
+            us.rename(s.name());
+            let dt = pl::DataType::Datetime(pl::TimeUnit::Microseconds, tz);
+            Ok(SeriesTree::Series(us.into_series().cast(&dt)?))
+        }
+        // nanotime is an integer64 of nanoseconds since epoch in UTC
+        Ok(SeriesTree::Series(s)) if x.inherits("nanotime") => Ok(SeriesTree::Series(
+            s.cast(&pl::DataType::Datetime(pl::TimeUnit::Nanoseconds, None))?,
+        )),
+        // hms is a difftime of seconds since midnight, must be matched before difftime
+        Ok(SeriesTree::Series(s)) if x.inherits("hms") => {
+            let mut ns: pl::Int64Chunked = s
+                .cast(&pl::DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|opt| opt.map(|val| (val * 1_000_000_000.0).round() as i64))
+                .collect();
+            ns.rename(s.name());
             Ok(SeriesTree::Series(
-                ((s * 1000f64).cast(&pl::DataType::Int64)?).cast(&dt)?,
+                ns.into_series().cast(&pl::DataType::Time)?,
             ))
         }
+        Ok(SeriesTree::Series(s)) if x.inherits("difftime") => {
+            let units = x
+                .get_attrib("units")
+                .and_then(|robj| robj.as_str().map(String::from));
+            let ms_factor: f64 = match units.as_deref() {
+                Some("secs") => 1_000.0,
+                Some("mins") => 60_000.0,
+                Some("hours") => 3_600_000.0,
+                Some("days") => 86_400_000.0,
+                Some("weeks") => 604_800_000.0,
+                _ => Err(pl::PolarsError::SchemaMismatch(
+                    "failure to convert class difftime as attribute units is not one of 'secs', 'mins', 'hours', 'days' or 'weeks'"
+                        .into(),
+                ))?,
+            };
+            let mut ms: pl::Int64Chunked = s
+                .cast(&pl::DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|opt| opt.map(|val| (val * ms_factor).round() as i64))
+                .collect();
+            ms.rename(s.name());
+            Ok(SeriesTree::Series(ms.into_series().cast(
+                &pl::DataType::Duration(pl::TimeUnit::Milliseconds),
+            )?))
+        }
         Ok(SeriesTree::Series(s)) if x.inherits("Date") => {
-            Ok(SeriesTree::Series(s.cast(&pl::DataType::Date)?))
+            // Date can hold fractional days, round down to the day they fall in
+            let mut days: pl::Int32Chunked = s
+                .cast(&pl::DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|opt| opt.map(|val| val.floor() as i32))
+                .collect();
+            days.rename(s.name());
+            Ok(SeriesTree::Series(
+                days.into_series().cast(&pl::DataType::Date)?,
+            ))
         }
         // Ok(SeriesTree::Series(s)) if x.inherits("ITime")=> {
         //     let dt = pl::DataType::Datetime(pl::TimeUnit::Milliseconds,tz);
@@ -247,12 +350,42 @@ fn recursive_robjname2series_tree(x: &Robj, name: &str) -> pl::PolarsResult<Seri
             ))
         }
 
+        // a matrix, or an array, is a fixed-width Array with an element per row
+        Ok(SeriesTree::Series(s))
+            if x.get_attrib("class").is_none()
+                && x.get_attrib("dim").map_or(false, |dim| dim.len() >= 2) =>
+        {
+            let dims: Vec<usize> = x
+                .get_attrib("dim")
+                .and_then(|dim| dim.as_integer_vector())
+                .expect("a dim attribute is an integer vector")
+                .into_iter()
+                .map(|dim| dim as usize)
+                .collect();
+            Ok(SeriesTree::Series(r_array_to_series(s, &dims, name)?))
+        }
+
         //     Ok(SeriesTree::Series((s.cast(&pl::DataType::Int64)?*1_000i64).cast(&dt)?))
         // },
         _ => series_result,
     }
 }
 
+// R stores a matrix column by column, reorder its values once in rows and wrap them in
+// a fixed size list per dimension after the first one
+fn r_array_to_series(s: pl::Series, dims: &[usize], name: &str) -> pl::PolarsResult<pl::Series> {
+    use polars_core::utils::arrow::array::{Array, FixedSizeListArray};
+    let idx = pl::IdxCa::from_vec("", crate::utils::row_major_to_col_major(dims));
+    let values = s.take(&idx)?.rechunk();
+    let mut array = values.chunks()[0].clone();
+    for width in dims[1..].iter().rev() {
+        let field = pl::ArrowField::new("item", array.data_type().clone(), true);
+        let data_type = pl::ArrowDataType::FixedSizeList(Box::new(field), *width);
+        array = FixedSizeListArray::try_new(data_type, array, None)?.boxed();
+    }
+    pl::Series::try_from((name, array))
+}
+
 // consume nested SeriesTree and return concatenated Series or an appropriate Error
 fn concat_series_tree(
     st: SeriesTree,
@@ -4,17 +4,29 @@ use pl::PolarsError as pl_error;
 use polars::prelude::{self as pl};
 use polars_core::datatypes::DataType;
 
+// how Struct Series are converted to R
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StructConversion {
+    Unnest,    // a list with a vector per field
+    Tag,       // as Unnest, but tagged with attribute "is_struct"
+    DataFrame, // a data.frame with a column per field, nested structs are data.frames too
+}
+
 //TODO throw a warning if i32 contains a lowerbound value which is the NA in R.
 pub fn pl_series_to_list(
     series: &pl::Series,
-    tag_structs: bool,
+    struct_conversion: StructConversion,
     int64_conversion: &str,
+    decimal_conversion: &str,
+    datetime_conversion: &str,
 ) -> pl::PolarsResult<Robj> {
     use pl::DataType::*;
     fn to_list_recursive(
         s: &pl::Series,
-        tag_structs: bool,
+        struct_conversion: StructConversion,
         int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
     ) -> pl::PolarsResult<Robj> {
         match s.dtype() {
             Float64 => s.f64().map(|ca| ca.into_iter().collect_robj()),
@@ -90,6 +102,26 @@ pub fn pl_series_to_list(
                     .map(|opt| opt.map(|val| val as f64))
                     .collect_robj()
             }),
+            Decimal(_, _) => {
+                let ca = s.decimal()?;
+                let scale = ca.scale();
+                match decimal_conversion {
+                    "double" => {
+                        let divisor = 10f64.powi(scale as i32);
+                        Ok(ca
+                            .into_iter()
+                            .map(|opt| opt.map(|val| val as f64 / divisor))
+                            .collect_robj())
+                    }
+                    "string" => Ok(ca
+                        .into_iter()
+                        .map(|opt| opt.map(|val| format_decimal(val, scale)))
+                        .collect_robj()),
+                    _ => Err(pl::PolarsError::InvalidOperation(
+                        "`decimal_conversion` must be one of 'double', 'string'".into(),
+                    )),
+                }
+            }
             String => s.str().map(|ca| ca.into_iter().collect_robj()),
 
             Boolean => s.bool().map(|ca| ca.into_iter().collect_robj()),
@@ -109,9 +141,12 @@ pub fn pl_series_to_list(
                     .set_class(["rpolars_raw_list", "list"])
                     .expect("this class label is always valid")
             }),
-            Categorical(_, _) => s
+            Enum(_, _) => s
                 .categorical()
-                .map(|ca| extendr_api::call!("factor", ca.iter_str().collect_robj()).unwrap()),
+                .map(|ca| categorical_to_factor(ca, false, true)),
+            Categorical(_, ordering) => s.categorical().map(|ca| {
+                categorical_to_factor(ca, *ordering == pl::CategoricalOrdering::Lexical, false)
+            }),
             List(_) => {
                 let mut v: Vec<extendr_api::Robj> = Vec::with_capacity(s.len());
                 let ca = s.list().unwrap();
@@ -124,8 +159,13 @@ pub fn pl_series_to_list(
                             Some(s) => {
                                 let s_ref = s.as_ref();
                                 // is safe because s is read to generate new Robj, then discarded.
-                                let inner_val =
-                                    to_list_recursive(s_ref, tag_structs, int64_conversion)?;
+                                let inner_val = to_list_recursive(
+                                    s_ref,
+                                    struct_conversion,
+                                    int64_conversion,
+                                    decimal_conversion,
+                                    datetime_conversion,
+                                )?;
                                 v.push(inner_val);
                             }
 
@@ -140,6 +180,54 @@ pub fn pl_series_to_list(
                 let l = extendr_api::List::from_iter(v.iter());
                 Ok(l.into_robj())
             }
+            // arrays of a primitive type are converted to a matrix, or an R array if nested,
+            // with a row per element
+            Array(_, _)
+                if {
+                    let leaf = array_leaf_dtype(s.dtype());
+                    leaf.is_numeric() || matches!(leaf, Boolean | String)
+                } =>
+            {
+                let mut dims = vec![s.len()];
+                let mut valid = vec![true; s.len()];
+                let mut values = s.clone();
+                while let Array(_, width) = values.dtype() {
+                    let width = *width;
+                    let ca = values.array()?;
+                    // values of null elements are undefined, mask them for all inner levels
+                    valid = valid
+                        .into_iter()
+                        .zip(ca.is_null().into_iter())
+                        .flat_map(|(valid, is_null)| {
+                            std::iter::repeat(valid && is_null != Some(true)).take(width)
+                        })
+                        .collect();
+                    dims.push(width);
+                    values = ca.get_inner();
+                }
+
+                // R stores a matrix column by column, polars row by row
+                let mut idx: Vec<Option<pl::IdxSize>> = vec![None; valid.len()];
+                for (row_major, col_major) in crate::utils::row_major_to_col_major(&dims)
+                    .into_iter()
+                    .enumerate()
+                {
+                    if valid[row_major] {
+                        idx[col_major as usize] = Some(row_major as pl::IdxSize);
+                    }
+                }
+                let idx: pl::IdxCa = idx.into_iter().collect();
+                let mut robj = to_list_recursive(
+                    &values.take(&idx)?,
+                    struct_conversion,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )?;
+                robj.set_attrib("dim", dims.iter().map(|dim| *dim as i32).collect_robj())
+                    .expect("internal error: attr dim failed");
+                Ok(robj)
+            }
             Array(_, _) => {
                 let mut v: Vec<extendr_api::Robj> = Vec::with_capacity(s.len());
                 let ca = s.array().unwrap();
@@ -149,8 +237,13 @@ pub fn pl_series_to_list(
                         Some(s) => {
                             let s_ref = s.as_ref();
                             // is safe because s is read to generate new Robj, then discarded.
-                            let inner_val =
-                                to_list_recursive(s_ref, tag_structs, int64_conversion)?;
+                            let inner_val = to_list_recursive(
+                                s_ref,
+                                struct_conversion,
+                                int64_conversion,
+                                decimal_conversion,
+                                datetime_conversion,
+                            )?;
                             v.push(inner_val);
                         }
 
@@ -166,11 +259,29 @@ pub fn pl_series_to_list(
             }
             Struct(_) => {
                 let df = s.clone().into_frame().unnest([s.name()]).unwrap();
-                let mut l = RPolarsDataFrame(df).to_list_result(int64_conversion)?;
+                // fields of structs in a data.frame are data.frames too, else tag them
+                let inner_conversion = match struct_conversion {
+                    StructConversion::DataFrame => StructConversion::DataFrame,
+                    _ => StructConversion::Tag,
+                };
+                let mut l = RPolarsDataFrame(df).to_list_result(
+                    inner_conversion,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )?;
 
                 //TODO contribute extendr_api set_attrib mutates &self, change signature to surprise anyone
-                if tag_structs {
-                    l.set_attrib("is_struct", true).unwrap();
+                match struct_conversion {
+                    StructConversion::Tag => {
+                        l.set_attrib("is_struct", true).unwrap();
+                    }
+                    StructConversion::DataFrame => {
+                        l.set_attrib("row.names", (1..=s.len() as i32).collect_robj())
+                            .unwrap();
+                        l.set_class(&["data.frame"]).unwrap();
+                    }
+                    StructConversion::Unnest => {}
                 };
 
                 Ok(l.into_robj())
@@ -189,48 +300,101 @@ pub fn pl_series_to_list(
                 .i64()
                 .map(|ca| {
                     ca.into_iter()
-                        .map(|opt| opt.map(|val| val as f64))
+                        .map(|opt| opt.map(|val| val as f64 / 1_000_000_000.0))
                         .collect_robj()
                 })
                 // TODO set_class and set_attrib reallocates the vector, find some way to modify without.
                 .map(|mut robj| {
-                    robj.set_class(&["PTime"])
-                        .expect("internal error: class label PTime failed")
+                    robj.set_class(&["hms", "difftime"])
+                        .expect("internal error: class label hms failed")
                 })
-                .map(|mut robj| robj.set_attrib("tu", "ns"))
-                .expect("internal error: attr tu failed")
+                .map(|mut robj| robj.set_attrib("units", "secs"))
+                .expect("internal error: attr units failed")
                 .map_err(|err| {
                     pl_error::ComputeError(
-                        format!("when converting polars Time to R PTime: {:?}", err).into(),
+                        format!("when converting polars Time to R hms: {:?}", err).into(),
                     )
                 }),
 
-            Datetime(tu, opt_tz) => {
+            Datetime(tu, opt_tz) => match datetime_conversion {
+                "POSIXct" => {
+                    let tu_f64: f64 = match tu {
+                        pl::TimeUnit::Nanoseconds => 1_000_000_000.0,
+                        pl::TimeUnit::Microseconds => 1_000_000.0,
+                        pl::TimeUnit::Milliseconds => 1_000.0,
+                    };
+
+                    //resolve timezone
+                    let tz = opt_tz.as_ref().map(|s| s.as_str()).unwrap_or("");
+                    s.cast(&Float64)?
+                        .f64()
+                        .map(|ca| {
+                            ca.into_iter()
+                                .map(|opt| opt.map(|val| val / tu_f64))
+                                .collect_robj()
+                        })
+                        // TODO set_class and set_attrib reallocates the vector, find some way to modify without.
+                        .map(|mut robj| {
+                            robj.set_class(&["POSIXct", "POSIXt"])
+                                .expect("internal error: class POSIXct label failed")
+                        })
+                        .map(|mut robj| robj.set_attrib("tzone", tz))
+                        .expect("internal error: attr tzone failed")
+                        .map_err(|err| {
+                            pl_error::ComputeError(
+                                format!("when converting polars Datetime to R POSIXct: {:?}", err)
+                                    .into(),
+                            )
+                        })
+                }
+                // nanotime is an integer64 of nanoseconds since epoch in UTC, the time zone is
+                // only used for printing so it is dropped
+                "nanotime" => {
+                    let bits = s
+                        .cast(&Datetime(pl::TimeUnit::Nanoseconds, opt_tz.clone()))?
+                        .cast(&Int64)?
+                        .i64()?
+                        .into_iter()
+                        .map(|opt| {
+                            let x = opt.unwrap_or(crate::utils::BIT64_NA_ENCODING);
+                            Some(f64::from_bits(x as u64))
+                        })
+                        .collect_robj()
+                        .set_class(&["integer64"])
+                        .expect("internal error could not set class label 'integer64'");
+                    R!("nanotime::nanotime({{&bits}})").map_err(|err| {
+                        pl_error::ComputeError(
+                            format!("when converting polars Datetime to R nanotime: {:?}", err)
+                                .into(),
+                        )
+                    })
+                }
+                _ => Err(pl::PolarsError::InvalidOperation(
+                    "`datetime_conversion` must be one of 'POSIXct', 'nanotime'".into(),
+                )),
+            },
+            Duration(tu) => {
                 let tu_f64: f64 = match tu {
                     pl::TimeUnit::Nanoseconds => 1_000_000_000.0,
                     pl::TimeUnit::Microseconds => 1_000_000.0,
                     pl::TimeUnit::Milliseconds => 1_000.0,
                 };
-
-                //resolve timezone
-                let tz = opt_tz.as_ref().map(|s| s.as_str()).unwrap_or("");
-                s.cast(&Float64)?
-                    .f64()
+                s.cast(&Int64)?
+                    .i64()
                     .map(|ca| {
                         ca.into_iter()
-                            .map(|opt| opt.map(|val| val / tu_f64))
+                            .map(|opt| opt.map(|val| val as f64 / tu_f64))
                             .collect_robj()
                     })
-                    // TODO set_class and set_attrib reallocates the vector, find some way to modify without.
                     .map(|mut robj| {
-                        robj.set_class(&["POSIXct", "POSIXt"])
-                            .expect("internal error: class POSIXct label failed")
+                        robj.set_class(&["difftime"])
+                            .expect("internal error: class label difftime failed")
                     })
-                    .map(|mut robj| robj.set_attrib("tzone", tz))
-                    .expect("internal error: attr tzone failed")
+                    .map(|mut robj| robj.set_attrib("units", "secs"))
+                    .expect("internal error: attr units failed")
                     .map_err(|err| {
                         pl_error::ComputeError(
-                            format!("when converting polars Datetime to R POSIXct: {:?}", err)
+                            format!("when converting polars Duration to R difftime: {:?}", err)
                                 .into(),
                         )
                     })
@@ -245,5 +409,70 @@ pub fn pl_series_to_list(
         }
     }
 
-    to_list_recursive(series, tag_structs, int64_conversion)
+    to_list_recursive(
+        series,
+        struct_conversion,
+        int64_conversion,
+        decimal_conversion,
+        datetime_conversion,
+    )
+}
+
+// the data type of the values of a possibly nested Array
+fn array_leaf_dtype(dtype: &DataType) -> &DataType {
+    match dtype {
+        DataType::Array(inner, _) => array_leaf_dtype(inner),
+        dtype => dtype,
+    }
+}
+
+// exact decimal representation of the integer `val` scaled by 10^-`scale`
+fn format_decimal(val: i128, scale: usize) -> String {
+    if scale == 0 {
+        return val.to_string();
+    }
+    let sign = if val < 0 { "-" } else { "" };
+    let abs = val.unsigned_abs();
+    let factor = 10u128.pow(scale as u32);
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / factor,
+        abs % factor,
+        width = scale
+    )
+}
+
+// the levels of the factor are the categories, in the order of their physical values, or sorted
+// if the ordering is lexical
+fn categorical_to_factor(ca: &pl::CategoricalChunked, lexical: bool, ordered: bool) -> Robj {
+    let rev_map = ca.get_rev_map();
+    let mut levels = crate::rdatatype::enum_categories(rev_map);
+    let codes = if !lexical && matches!(**rev_map, pl::RevMapping::Local(..)) {
+        ca.physical()
+            .into_iter()
+            .map(|opt| opt.map(|idx| idx as i32 + 1))
+            .collect_robj()
+    } else {
+        if lexical {
+            levels.sort_unstable();
+        }
+        let positions: std::collections::HashMap<&str, i32> = levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| (level.as_str(), i as i32 + 1))
+            .collect();
+        ca.iter_str()
+            .map(|opt| opt.map(|x| positions[x]))
+            .collect_robj()
+    };
+    let class: &[&str] = if ordered {
+        &["ordered", "factor"]
+    } else {
+        &["factor"]
+    };
+    codes
+        .set_attrib("levels", levels)
+        .and_then(|mut robj| robj.set_class(class))
+        .expect("internal error: class label factor failed")
 }
@@ -5,12 +5,21 @@ use crate::conversion::strings_to_smartstrings;
 
 use crate::lazy::dsl::RPolarsExpr;
 use crate::lazy::dsl::*;
+use crate::lazy::r_function::{plan_has_r_function, R_FUNCTION_SERIALIZE_HINT};
+use crate::lazy::stream::{LazyFrameBatchIterator, SunkBatchIterator};
 
+use crate::rdataframe::compression::{new_text_compression, sink_compressed};
+use crate::rdataframe::write_parquet::{write_parquet_batches, ParquetFileWriter};
+use crate::rdataframe::write_partitioned::{check_empty_dir, write_partitioned};
 use crate::rdataframe::RPolarsDataFrame as RDF;
-use crate::rdatatype::{new_ipc_compression, new_parquet_compression, RPolarsDataType};
+use crate::rdatatype::{
+    new_cloud_options, new_ipc_compression, new_parquet_compression, RPolarsDataType,
+};
 use crate::robj_to;
-use crate::rpolarserr::{polars_to_rpolars_err, RPolarsErr, RResult, WithRctx};
-use crate::utils::{r_result_list, try_f64_into_usize};
+use crate::rpolarserr::{
+    polars_to_rpolars_err, rerr, serde_json_to_rpolars_err, RPolarsErr, RResult, WithRctx,
+};
+use crate::utils::{r_result_list, robj_str_ptr_to_usize, try_f64_into_usize, TempPath};
 use extendr_api::prelude::*;
 use pl::{AsOfOptions, Duration, RollingGroupOptions};
 use polars::frame::explode::MeltArgs;
@@ -57,6 +66,25 @@ impl RPolarsLazyFrame {
             .map(|val| format!("{:?}", val))
     }
 
+    pub fn serialize_plan(&self) -> RResult<String> {
+        if plan_has_r_function(&self.0.logical_plan) {
+            return rerr()
+                .plain("the LazyFrame contains an R function")
+                .hint(R_FUNCTION_SERIALIZE_HINT)
+                .when("serializing the LazyFrame plan to JSON");
+        }
+        serde_json::to_string(&self.0.logical_plan)
+            .map_err(serde_json_to_rpolars_err)
+            .when("serializing the LazyFrame plan to JSON")
+    }
+
+    pub fn deserialize_plan(json: Robj) -> RResult<Self> {
+        serde_json::from_str::<pl::LogicalPlan>(robj_to!(str, json)?)
+            .map_err(serde_json_to_rpolars_err)
+            .when("deserializing a LazyFrame plan from JSON")
+            .map(|lp| pl::LazyFrame::from(lp).into())
+    }
+
     pub fn describe_optimized_plan(&self) -> List {
         let result = self.0.describe_optimized_plan().map(|opt_plan| {
             rprintln!("{}", opt_plan);
@@ -68,6 +96,44 @@ impl RPolarsLazyFrame {
         collect_with_r_func_support(self.clone().0)
     }
 
+    pub fn export_stream(&self, stream_ptr: Robj, tmp_dir: Robj) -> RResult<()> {
+        use polars_core::utils::arrow;
+        // the query is sunk by the streaming engine, which cannot call back into R
+        if plan_has_r_function(&self.0.logical_plan) {
+            return rerr()
+                .plain("the LazyFrame contains an R function")
+                .hint("R functions, e.g. in $map_batches(), cannot be streamed, use $collect() instead")
+                .when("exporting the LazyFrame as an Arrow C stream");
+        }
+        let stream_out_ptr_addr = robj_str_ptr_to_usize(&stream_ptr)?;
+        let schema = self
+            .0
+            .schema()
+            .map_err(polars_to_rpolars_err)?
+            .to_arrow(false);
+        let data_type = arrow::datatypes::ArrowDataType::Struct(schema.fields);
+        let field = pl::ArrowField::new("", data_type, false);
+
+        // the query is sunk into a temporary file in the R session's tempdir(), so that the
+        // batches are read in the order of the query without holding all of them in memory
+        let tmp_dir = robj_to!(String, tmp_dir)?;
+        let file = TempPath::next_to(&std::path::Path::new(&tmp_dir).join("polars-stream"));
+        let iter = LazyFrameBatchIterator::new(self.0.clone(), file)
+            .map_err(polars_to_rpolars_err)
+            .when("exporting the LazyFrame as an Arrow C stream")?;
+        let iter_boxed = Box::new(iter);
+        let mut stream = arrow::ffi::export_iterator(iter_boxed, field);
+        let stream_out_ptr = stream_out_ptr_addr as *mut arrow::ffi::ArrowArrayStream;
+        unsafe {
+            std::ptr::swap_nonoverlapping(
+                stream_out_ptr,
+                &mut stream as *mut arrow::ffi::ArrowArrayStream,
+                1,
+            );
+        }
+        Ok(())
+    }
+
     pub fn collect_in_background(&self) -> crate::rbackground::RPolarsRThreadHandle<RResult<RDF>> {
         use crate::rbackground::*;
         let dup = self.clone();
@@ -90,6 +156,11 @@ impl RPolarsLazyFrame {
         row_group_size: Robj,
         data_pagesize_limit: Robj,
         maintain_order: Robj,
+        storage_options: Robj,
+        retries: Robj,
+        partition_by: Robj,
+        max_rows_per_file: Robj,
+        metadata: Robj,
     ) -> RResult<()> {
         let pqwo = polars::prelude::ParquetWriteOptions {
             compression: new_parquet_compression(compression_method, compression_level)?,
@@ -98,10 +169,69 @@ impl RPolarsLazyFrame {
             data_pagesize_limit: robj_to!(Option, usize, data_pagesize_limit)?,
             maintain_order: robj_to!(bool, maintain_order)?,
         };
-        self.0
-            .clone()
-            .sink_parquet(robj_to!(String, path)?.into(), pqwo)
-            .map_err(polars_to_rpolars_err)
+        let path = robj_to!(String, path)?;
+        let cloud_options = new_cloud_options(&path, storage_options, retries)?;
+        let partition_by = robj_to!(Option, Vec, String, partition_by)?;
+        let metadata = crate::utils::robj_to_named_strings(metadata).bad_arg("metadata")?;
+
+        // the streaming engine can neither write partitions nor metadata, so the query is sunk
+        // into a temporary file, which is then written batch by batch
+        if partition_by.is_some() || !metadata.is_empty() {
+            if cloud_options.is_some() {
+                return rerr()
+                    .bad_val(path)
+                    .plain("`partition_by` and `metadata` can only be used with local paths");
+            }
+            if plan_has_r_function(&self.0.logical_plan) {
+                return rerr()
+                    .plain("the LazyFrame contains an R function")
+                    .hint("use $collect() and then $write_parquet() instead")
+                    .when("sinking the LazyFrame with `partition_by` or `metadata`");
+            }
+            let path = std::path::Path::new(&path);
+            return match partition_by {
+                Some(by) => {
+                    let max_rows_per_file = robj_to!(Option, usize, max_rows_per_file)?;
+                    check_empty_dir(path).and_then(|_| {
+                        std::fs::create_dir_all(path)?;
+                        let batches = SunkBatchIterator::new(
+                            self.0.clone(),
+                            TempPath::next_to(path),
+                            pqwo.maintain_order,
+                        )?;
+                        write_partitioned(
+                            batches,
+                            path,
+                            &by,
+                            max_rows_per_file,
+                            "parquet",
+                            |f, schema| ParquetFileWriter::new(f, schema, &pqwo, &metadata),
+                        )
+                    })
+                }
+                None => self.0.schema().and_then(|schema| {
+                    let batches = SunkBatchIterator::new(
+                        self.0.clone(),
+                        TempPath::next_to(path),
+                        pqwo.maintain_order,
+                    )?;
+                    let f = std::fs::File::create(path)?;
+                    write_parquet_batches(batches, f, &schema, &pqwo, &metadata)
+                }),
+            }
+            .map(|_| ())
+            .map_err(polars_to_rpolars_err);
+        }
+
+        match cloud_options {
+            Some(cloud_options) => {
+                self.0
+                    .clone()
+                    .sink_parquet_cloud(path, Some(cloud_options), pqwo)
+            }
+            None => self.0.clone().sink_parquet(path.into(), pqwo),
+        }
+        .map_err(polars_to_rpolars_err)
     }
 
     fn sink_ipc(&self, path: Robj, compression_method: Robj, maintain_order: Robj) -> RResult<()> {
@@ -132,6 +262,7 @@ impl RPolarsLazyFrame {
         null_value: Robj,
         quote_style: Robj,
         maintain_order: Robj,
+        compression: Robj,
     ) -> RResult<()> {
         // using robj_to!() directly in SerializeOptions doesn't work
         let date_format = robj_to!(Option, String, date_format)?;
@@ -168,19 +299,21 @@ impl RPolarsLazyFrame {
             serialize_options,
         };
 
-        self.0
-            .clone()
-            .sink_csv(robj_to!(String, path)?.into(), options)
-            .map_err(polars_to_rpolars_err)
+        let path = robj_to!(String, path)?;
+        sink_compressed(&path, new_text_compression(compression)?, |path| {
+            self.0.clone().sink_csv(path.into(), options)
+        })
+        .map_err(polars_to_rpolars_err)
     }
 
-    fn sink_json(&self, path: Robj, maintain_order: Robj) -> RResult<()> {
+    fn sink_json(&self, path: Robj, maintain_order: Robj, compression: Robj) -> RResult<()> {
         let maintain_order = robj_to!(bool, maintain_order)?;
         let options = pl::JsonWriterOptions { maintain_order };
-        self.0
-            .clone()
-            .sink_json(robj_to!(String, path)?.into(), options)
-            .map_err(polars_to_rpolars_err)
+        let path = robj_to!(String, path)?;
+        sink_compressed(&path, new_text_compression(compression)?, |path| {
+            self.0.clone().sink_json(path.into(), options)
+        })
+        .map_err(polars_to_rpolars_err)
     }
 
     fn first(&self) -> Self {
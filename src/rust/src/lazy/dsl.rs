@@ -1,11 +1,13 @@
 use crate::concurrent::RFnSignature;
+use crate::lazy::r_function::{expr_has_r_function, R_FUNCTION_SERIALIZE_HINT};
 use crate::rdatatype::{
     literal_to_any_value, new_rolling_cov_options, parse_fill_null_strategy, robj_to_timeunit,
     RPolarsDataType, RPolarsDataTypeVector,
 };
 use crate::robj_to;
 use crate::rpolarserr::{
-    polars_to_rpolars_err, rerr, rpolars_to_polars_err, RResult, Rctx, WithRctx,
+    polars_to_rpolars_err, rerr, rpolars_to_polars_err, serde_json_to_rpolars_err, RResult, Rctx,
+    WithRctx,
 };
 use crate::series::RPolarsSeries;
 use crate::utils::extendr_concurrent::{ParRObj, ThreadCom};
@@ -94,7 +96,9 @@ impl RPolarsExpr {
             (Rtype::Raw, _) => Ok(dsl::lit(robj_to_binary_vec(robj)?)), // Raw in R is seen as a vector of bytes, in polars it is a Literal, not wrapped in a Series.
             (_, rlen) if rlen != 1 => to_series_then_lit(robj),
             (Rtype::List, _) => to_series_then_lit(robj),
-            (_, _) if robj_inherits(&robj, ["POSIXct", "PTime", "Date"]) => {
+            (_, _)
+                if robj_inherits(&robj, ["POSIXct", "PTime", "Date", "difftime", "nanotime"]) =>
+            {
                 to_series_then_lit(robj)
             }
 
@@ -2463,6 +2467,25 @@ impl RPolarsExpr {
         Ok(format!("{e}"))
     }
 
+    pub fn serialize(&self) -> RResult<String> {
+        if expr_has_r_function(&self.0) {
+            return rerr()
+                .plain("the Expr contains an R function")
+                .hint(R_FUNCTION_SERIALIZE_HINT)
+                .when("serializing the Expr to JSON");
+        }
+        serde_json::to_string(&self.0)
+            .map_err(serde_json_to_rpolars_err)
+            .when("serializing the Expr to JSON")
+    }
+
+    pub fn deserialize(json: Robj) -> RResult<RPolarsExpr> {
+        serde_json::from_str::<pl::Expr>(robj_to!(str, json)?)
+            .map_err(serde_json_to_rpolars_err)
+            .when("deserializing an Expr from JSON")
+            .map(RPolarsExpr)
+    }
+
     fn cat_set_ordering(&self, ordering: Robj) -> Result<RPolarsExpr, String> {
         let ordering = robj_to!(Map, str, ordering, |s| {
             Ok(crate::rdatatype::new_categorical_ordering(s).map_err(Rctx::Plain)?)
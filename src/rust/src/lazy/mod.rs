@@ -3,6 +3,8 @@
 use extendr_api::*;
 pub mod dataframe;
 pub mod dsl;
+pub mod r_function;
+pub mod stream;
 pub mod whenthen;
 //#[cfg(feature = "meta")]
 //mod meta;
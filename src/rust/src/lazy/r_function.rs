@@ -0,0 +1,75 @@
+//detect R functions embedded in expressions and query plans
+
+use polars::prelude as pl;
+use polars_plan::logical_plan::FunctionNode;
+
+// R functions can neither be serialized nor be called outside of collect_with_r_func_support()
+pub const R_FUNCTION_SERIALIZE_HINT: &str =
+    "R functions, e.g. in $map_batches(), pl$fold() or pl$reduce(), cannot be serialized";
+
+pub fn expr_has_r_function(expr: &pl::Expr) -> bool {
+    expr.into_iter()
+        .any(|e| matches!(e, pl::Expr::AnonymousFunction { .. }))
+}
+
+fn exprs_have_r_function<'a>(mut exprs: impl Iterator<Item = &'a pl::Expr>) -> bool {
+    exprs.any(expr_has_r_function)
+}
+
+// Walks the plan and its inputs. There is no wildcard arm, so that a variant added by a new
+// version of polars fails to compile until it is handled here, rather than being taken for a
+// plan without R functions.
+pub fn plan_has_r_function(plan: &pl::LogicalPlan) -> bool {
+    use pl::LogicalPlan::*;
+    match plan {
+        Selection { input, predicate } => {
+            expr_has_r_function(predicate) || plan_has_r_function(input)
+        }
+        Projection { expr, input, .. } => {
+            exprs_have_r_function(expr.iter()) || plan_has_r_function(input)
+        }
+        HStack { exprs, input, .. } => {
+            exprs_have_r_function(exprs.iter()) || plan_has_r_function(input)
+        }
+        Aggregate {
+            input,
+            keys,
+            aggs,
+            apply,
+            ..
+        } => {
+            apply.is_some()
+                || exprs_have_r_function(keys.iter())
+                || exprs_have_r_function(aggs.iter())
+                || plan_has_r_function(input)
+        }
+        Join {
+            input_left,
+            input_right,
+            left_on,
+            right_on,
+            ..
+        } => {
+            exprs_have_r_function(left_on.iter().chain(right_on.iter()))
+                || plan_has_r_function(input_left)
+                || plan_has_r_function(input_right)
+        }
+        Sort {
+            input, by_column, ..
+        } => exprs_have_r_function(by_column.iter()) || plan_has_r_function(input),
+        MapFunction { input, function } => {
+            matches!(function, FunctionNode::Opaque { .. }) || plan_has_r_function(input)
+        }
+        Scan { predicate, .. } => predicate.iter().any(expr_has_r_function),
+        DataFrameScan { selection, .. } => selection.iter().any(expr_has_r_function),
+        Union { inputs, .. } | HConcat { inputs, .. } => inputs.iter().any(plan_has_r_function),
+        ExtContext {
+            input, contexts, ..
+        } => plan_has_r_function(input) || contexts.iter().any(plan_has_r_function),
+        Cache { input, .. } | Distinct { input, .. } | Slice { input, .. } | Sink { input, .. } => {
+            plan_has_r_function(input)
+        }
+        // a plan that failed to build is never run
+        Error { .. } => false,
+    }
+}
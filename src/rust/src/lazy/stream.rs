@@ -0,0 +1,82 @@
+//stream the results of a query batch by batch
+
+use crate::rdataframe::OwnedDataFrameIterator;
+use crate::utils::TempPath;
+use polars::prelude::{self as pl, PolarsResult};
+use polars_core::utils::arrow;
+use polars_core::StringCacheHolder;
+use std::fs::File;
+use std::io::BufReader;
+
+// Yields the batches of a sunk query as struct arrays, for the Arrow C stream interface.
+// Dropping the iterator, i.e. releasing the stream, removes the temporary file.
+pub struct LazyFrameBatchIterator {
+    batches: SunkBatchIterator,
+    current: Option<OwnedDataFrameIterator>,
+}
+
+impl LazyFrameBatchIterator {
+    pub fn new(lf: pl::LazyFrame, file: TempPath) -> PolarsResult<Self> {
+        Ok(Self {
+            batches: SunkBatchIterator::new(lf, file, true)?,
+            current: None,
+        })
+    }
+}
+
+impl Iterator for LazyFrameBatchIterator {
+    type Item = PolarsResult<Box<dyn arrow::array::Array>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(array) = self.current.as_mut().and_then(|it| it.next()) {
+                return Some(array);
+            }
+            match self.batches.next()? {
+                Ok(df) => self.current = Some(OwnedDataFrameIterator::new(df)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+// Sinks a query with the streaming engine into the temporary IPC `file`, and yields the batches
+// of that file. The sink keeps the optimizations set on the LazyFrame, and yields the batches in
+// the order of the query if `maintain_order`. The string cache is held so that the categoricals
+// of different batches can be stacked. The file is removed when the iterator is dropped.
+pub struct SunkBatchIterator {
+    reader: arrow::io::ipc::read::FileReader<BufReader<File>>,
+    fields: Vec<arrow::datatypes::Field>,
+    _string_cache: StringCacheHolder,
+    _file: TempPath,
+}
+
+impl SunkBatchIterator {
+    pub fn new(lf: pl::LazyFrame, file: TempPath, maintain_order: bool) -> PolarsResult<Self> {
+        let string_cache = StringCacheHolder::hold();
+        let options = pl::IpcWriterOptions {
+            compression: None,
+            maintain_order,
+        };
+        lf.sink_ipc(file.path().to_path_buf(), options)?;
+
+        let mut reader = BufReader::new(File::open(file.path())?);
+        let metadata = arrow::io::ipc::read::read_file_metadata(&mut reader)?;
+        let fields = metadata.schema.fields.clone();
+        Ok(Self {
+            reader: arrow::io::ipc::read::FileReader::new(reader, metadata, None, None),
+            fields,
+            _string_cache: string_cache,
+            _file: file,
+        })
+    }
+}
+
+impl Iterator for SunkBatchIterator {
+    type Item = PolarsResult<pl::DataFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.reader.next()?;
+        Some(chunk.and_then(|chunk| pl::DataFrame::try_from((chunk, self.fields.as_slice()))))
+    }
+}
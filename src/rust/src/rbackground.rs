@@ -4,6 +4,7 @@ use crate::rpolarserr::rerr;
 use crate::rpolarserr::{
     extendr_to_rpolars_err, polars_to_rpolars_err, rdbg, RPolarsErr, RResult, Rctx, WithRctx,
 };
+use base64::engine::{general_purpose::STANDARD, Engine};
 use extendr_api::{
     call, eval_string, extendr, extendr_module, list, pairlist, symbol::class_symbol, Attributes,
     Conversions, Length, List, Operators, Pairlist, Rinternals, Robj, NULL, R,
@@ -94,10 +95,18 @@ pub fn deserialize_robj(bits: Vec<u8>) -> RResult<Robj> {
 }
 
 pub fn serialize_dataframe(dataframe: &mut polars::prelude::DataFrame) -> RResult<Vec<u8>> {
+    serialize_dataframe_with_compression(dataframe, None)
+}
+
+pub fn serialize_dataframe_with_compression(
+    dataframe: &mut polars::prelude::DataFrame,
+    compression: Option<polars::prelude::IpcCompression>,
+) -> RResult<Vec<u8>> {
     use polars::io::SerWriter;
 
     let mut dump = Vec::new();
     polars::io::ipc::IpcWriter::new(&mut dump)
+        .with_compression(compression)
         .finish(dataframe)
         .map_err(polars_to_rpolars_err)?;
     Ok(dump)
@@ -112,7 +121,14 @@ pub fn deserialize_dataframe(bits: &[u8]) -> RResult<polars::prelude::DataFrame>
 }
 
 pub fn serialize_series(series: PSeries) -> RResult<Vec<u8>> {
-    serialize_dataframe(&mut std::iter::once(series).collect())
+    serialize_series_with_compression(series, None)
+}
+
+pub fn serialize_series_with_compression(
+    series: PSeries,
+    compression: Option<polars::prelude::IpcCompression>,
+) -> RResult<Vec<u8>> {
+    serialize_dataframe_with_compression(&mut std::iter::once(series).collect(), compression)
 }
 
 pub fn deserialize_series(bits: &[u8]) -> RResult<PSeries> {
@@ -579,6 +595,34 @@ pub fn test_rthreadhandle() -> RPolarsRThreadHandle<RResult<RPolarsDataFrame>> {
     })
 }
 
+// R's serialize() only lets a refhook replace an external pointer with a character vector, so the
+// raw IPC bytes of a DataFrame or Series are base64 encoded, which adds a third to their size. A
+// string holds at most 2^31 - 1 bytes, so they are split into strings of BASE64_CHUNK_SIZE bytes
+// each, a multiple of 3 so that no chunk but the last one is padded.
+const BASE64_CHUNK_SIZE: usize = 3 << 28;
+
+#[extendr]
+pub fn raw_to_base64(x: Robj) -> RResult<Vec<String>> {
+    let bits = robj_to!(Raw, x)?;
+    Ok(bits
+        .chunks(BASE64_CHUNK_SIZE)
+        .map(|chunk| STANDARD.encode(chunk))
+        .collect())
+}
+
+#[extendr]
+pub fn base64_to_raw(x: Robj) -> RResult<Robj> {
+    let mut bits = Vec::new();
+    for chunk in robj_to!(Vec, String, x)? {
+        if let Err(err) = STANDARD.decode_vec(chunk, &mut bits) {
+            return rerr()
+                .plain(format!("invalid base64 string: {err}"))
+                .bad_arg("x");
+        }
+    }
+    Ok(extendr_api::prelude::Raw::from_bytes(&bits).into())
+}
+
 #[extendr]
 pub fn test_serde_df(df: &RPolarsDataFrame) -> RResult<RPolarsDataFrame> {
     let x = serialize_dataframe(&mut df.0.clone())?;
@@ -595,5 +639,7 @@ extendr_module! {
     fn handle_background_request;
     fn test_rbackgroundhandler;
     fn test_rthreadhandle;
+    fn raw_to_base64;
+    fn base64_to_raw;
     fn test_serde_df;
 }
@@ -0,0 +1,280 @@
+//gzip and zstd compression of text files (CSV, NDJSON, JSON)
+
+use crate::robj_to;
+use crate::rpolarserr::*;
+use crate::utils::TempPath;
+use extendr_api::prelude::*;
+use polars::prelude::{self as pl, PolarsResult};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug)]
+pub enum TextCompression {
+    Gzip,
+    Zstd,
+}
+
+pub fn new_text_compression(compression: Robj) -> RResult<Option<TextCompression>> {
+    use TextCompression::*;
+    robj_to!(Option, String, compression)?
+        .map(|c| match c.as_str() {
+            "uncompressed" => Ok(None),
+            "gzip" => Ok(Some(Gzip)),
+            "zstd" => Ok(Some(Zstd)),
+            m => rerr()
+                .bad_val(m)
+                .misvalued("should be one of ['uncompressed', 'gzip', 'zstd']"),
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+// Let `write` write into `w`, compressed if requested, and return `w`.
+fn encode<W, F>(mut w: W, compression: Option<TextCompression>, write: F) -> PolarsResult<W>
+where
+    W: Write,
+    F: FnOnce(&mut dyn Write) -> PolarsResult<()>,
+{
+    match compression {
+        None => {
+            write(&mut w)?;
+            Ok(w)
+        }
+        Some(TextCompression::Gzip) => {
+            let mut encoder = flate2::write::GzEncoder::new(w, flate2::Compression::default());
+            write(&mut encoder)?;
+            Ok(encoder.finish()?)
+        }
+        Some(TextCompression::Zstd) => {
+            let mut encoder = zstd::Encoder::new(w, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+            write(&mut encoder)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+// Create the file `path` and let `write` write into it, compressed if requested.
+pub fn write_compressed<F>(
+    path: &str,
+    compression: Option<TextCompression>,
+    write: F,
+) -> PolarsResult<()>
+where
+    F: FnOnce(&mut dyn Write) -> PolarsResult<()>,
+{
+    let f = std::io::BufWriter::new(File::create(path)?);
+    encode(f, compression, write)?.flush()?;
+    Ok(())
+}
+
+// Write into the file `path`, or, if `path` is None, into a buffer returned as a raw vector.
+pub fn write_text_output<F>(
+    path: Option<&str>,
+    compression: Option<TextCompression>,
+    write: F,
+) -> RResult<Robj>
+where
+    F: FnOnce(&mut dyn Write) -> PolarsResult<()>,
+{
+    match path {
+        Some(path) => write_compressed(path, compression, write).map(|_| NULL.into()),
+        None => encode(Vec::new(), compression, write).map(|buf| r!(Raw::from_bytes(&buf))),
+    }
+    .map_err(polars_to_rpolars_err)
+}
+
+// Run `sink` on `path`, or, if compression is requested, on a temporary file next to `path` that
+// is compressed into `path` afterwards. This way the query is still streamed and its output never
+// has to fit in memory.
+pub fn sink_compressed<F>(
+    path: &str,
+    compression: Option<TextCompression>,
+    sink: F,
+) -> PolarsResult<()>
+where
+    F: FnOnce(&str) -> PolarsResult<()>,
+{
+    let Some(compression) = compression else {
+        return sink(path);
+    };
+    let tmp = TempPath::next_to(Path::new(path));
+    sink(&tmp.path().to_string_lossy())?;
+    let mut reader = BufReader::new(File::open(tmp.path())?);
+    write_compressed(path, Some(compression), |w| {
+        std::io::copy(&mut reader, w)?;
+        Ok(())
+    })
+}
+
+fn detect_compression(magic: &[u8]) -> Option<&'static str> {
+    match magic {
+        [0x1f, 0x8b, ..] => Some("gzip"),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some("zstd"),
+        _ => None,
+    }
+}
+
+// The compression of the file `path`, detected from its first bytes. A file that cannot be read
+// counts as uncompressed, the scanner reports the error.
+fn file_compression(path: &Path) -> Option<&'static str> {
+    let mut magic = [0u8; 4];
+    let n = File::open(path).and_then(|mut f| f.read(&mut magic)).ok()?;
+    detect_compression(&magic[..n])
+}
+
+// Copy the first `n_lines` lines of `reader` into `out`, or all of it if `n_lines` is None.
+fn copy_lines(
+    reader: impl Read,
+    out: &mut impl Write,
+    n_lines: Option<usize>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let Some(n_lines) = n_lines else {
+        std::io::copy(&mut reader, out)?;
+        return Ok(());
+    };
+    let mut line = Vec::new();
+    for _ in 0..n_lines {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        out.write_all(&line)?;
+    }
+    Ok(())
+}
+
+// If the file `path` is compressed with gzip or zstd, decompress it, or only its first `n_lines`
+// lines, into a temporary file in `tmp_dir`, which is removed again when the returned TempPath
+// is dropped.
+pub fn decompressed(
+    path: &Path,
+    tmp_dir: &Path,
+    n_lines: Option<usize>,
+) -> PolarsResult<Option<TempPath>> {
+    let Some(kind) = file_compression(path) else {
+        return Ok(None);
+    };
+    let name = path.file_name().unwrap_or_default();
+    let tmp = TempPath::next_to(&tmp_dir.join(name));
+    let f = BufReader::new(File::open(path)?);
+    let decoder: Box<dyn Read> = match kind {
+        "gzip" => Box::new(flate2::bufread::MultiGzDecoder::new(f)),
+        _ => Box::new(zstd::Decoder::with_buffer(f)?),
+    };
+    let mut out = std::io::BufWriter::new(File::create(tmp.path())?);
+    copy_lines(decoder, &mut out, n_lines)
+        .and_then(|_| out.flush())
+        .map_err(|e| {
+            pl::polars_err!(ComputeError: "decompressing the {kind} file '{}': {e}", path.display())
+        })?;
+    Ok(Some(tmp))
+}
+
+// Paths of the files to scan, decompressed ones replaced by their temporary copy.
+fn decompressed_paths(
+    paths: &[PathBuf],
+    tmp_dir: &Path,
+) -> PolarsResult<(Vec<PathBuf>, Vec<TempPath>)> {
+    let mut scanned = Vec::with_capacity(paths.len());
+    let mut tmps = Vec::new();
+    for path in paths {
+        match decompressed(path, tmp_dir, None)? {
+            Some(tmp) => {
+                scanned.push(tmp.path().to_path_buf());
+                tmps.push(tmp);
+            }
+            None => scanned.push(path.clone()),
+        }
+    }
+    Ok((scanned, tmps))
+}
+
+type ScanFn = dyn Fn(Vec<PathBuf>) -> PolarsResult<pl::LazyFrame> + Send + Sync;
+
+// A scan of files of which some are compressed. They are only decompressed when the scan runs,
+// into temporary files that are removed once it is done, so that a LazyFrame of compressed
+// files leaves no decompressed copies behind. The filters, projections and slices pushed down
+// by the optimizer are passed on to the scan of the decompressed files.
+struct DecompressingScan {
+    paths: Vec<PathBuf>,
+    tmp_dir: PathBuf,
+    scan: Box<ScanFn>,
+}
+
+impl pl::AnonymousScan for DecompressingScan {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn scan(&self, args: pl::AnonymousScanArgs) -> PolarsResult<pl::DataFrame> {
+        let (paths, _tmps) = decompressed_paths(&self.paths, &self.tmp_dir)?;
+        let mut lf = (self.scan)(paths)?;
+        if let Some(predicate) = args.predicate {
+            lf = lf.filter(predicate);
+        }
+        if let Some(columns) = &args.with_columns {
+            lf = lf.select(columns.iter().map(|name| pl::col(name)).collect::<Vec<_>>());
+        }
+        if let Some(n) = args.n_rows {
+            lf = lf.limit(n as pl::IdxSize);
+        }
+        lf.collect()
+    }
+
+    fn allows_predicate_pushdown(&self) -> bool {
+        true
+    }
+
+    fn allows_projection_pushdown(&self) -> bool {
+        true
+    }
+
+    fn allows_slice_pushdown(&self) -> bool {
+        true
+    }
+}
+
+// Scan `paths` with `scan`. If any of the files, or of the files matched by a glob pattern, is
+// compressed with gzip or zstd, the scan is wrapped in a DecompressingScan that decompresses
+// into `tmp_dir`, the R session's tempdir(). Its schema is inferred from the first file, as the
+// scanners do, but only the first `schema_lines` lines of it are decompressed for that, so that
+// building the LazyFrame stays cheap for large files. `schema_lines` is None if the scanner
+// infers the schema from all rows.
+pub fn scan_maybe_compressed<F>(
+    paths: Vec<String>,
+    tmp_dir: PathBuf,
+    schema_lines: Option<usize>,
+    scan: F,
+) -> RResult<pl::LazyFrame>
+where
+    F: Fn(Vec<PathBuf>) -> PolarsResult<pl::LazyFrame> + Send + Sync + 'static,
+{
+    let expanded: Vec<PathBuf> = crate::utils::expand_paths(paths.clone())?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    if !expanded.iter().any(|path| file_compression(path).is_some()) {
+        return scan(paths.into_iter().map(PathBuf::from).collect()).map_err(polars_to_rpolars_err);
+    }
+
+    let schema = decompressed(&expanded[0], &tmp_dir, schema_lines)
+        .and_then(|prefix| match &prefix {
+            Some(prefix) => scan(vec![prefix.path().to_path_buf()])?.schema(),
+            None => scan(expanded[..1].to_vec())?.schema(),
+        })
+        .map_err(polars_to_rpolars_err)
+        .when("inferring the schema of the compressed file")?;
+    let args = pl::ScanArgsAnonymous {
+        schema: Some(schema),
+        name: "COMPRESSED FILES",
+        ..Default::default()
+    };
+    let scan = DecompressingScan {
+        paths: expanded,
+        tmp_dir,
+        scan: Box::new(scan),
+    };
+    pl::LazyFrame::anonymous_scan(std::sync::Arc::new(scan), args).map_err(polars_to_rpolars_err)
+}
@@ -1,20 +1,29 @@
 use extendr_api::{extendr, prelude::*, rprintln, Rinternals};
 use polars::prelude::{self as pl, IntoLazy, SerWriter};
 use std::result::Result;
+pub mod compression;
+pub mod read_avro;
 pub mod read_csv;
 pub mod read_ipc;
 pub mod read_ndjson;
 pub mod read_parquet;
+pub mod write_parquet;
+pub mod write_partitioned;
 use crate::conversion_r_to_s::robjname2series;
 use crate::lazy;
 use crate::rdatatype;
-use crate::rdatatype::{new_parquet_compression, RPolarsDataType};
+use crate::rdatatype::{
+    new_avro_compression, new_ipc_compression, new_parquet_compression, RPolarsDataType,
+};
 use crate::robj_to;
 use crate::rpolarserr::*;
+use compression::{new_text_compression, write_text_output};
 use either::Either;
 pub use lazy::dataframe::*;
+use write_parquet::{write_parquet_file, ParquetFileWriter};
+use write_partitioned::write_partitioned;
 
-use crate::conversion_s_to_r::pl_series_to_list;
+use crate::conversion_s_to_r::{pl_series_to_list, StructConversion};
 pub use crate::series::*;
 
 use arrow::datatypes::ArrowDataType;
@@ -229,12 +238,23 @@ impl RPolarsDataFrame {
     //     self.0.compare
     // }
 
-    pub fn to_list(&self, int64_conversion: &str) -> List {
+    pub fn to_list(
+        &self,
+        int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
+    ) -> List {
         let robj_vec_res: Result<Vec<Robj>, _> = collect_hinted_result(
             self.0.width(),
-            self.0
-                .iter()
-                .map(|x| pl_series_to_list(x, false, int64_conversion)),
+            self.0.iter().map(|x| {
+                pl_series_to_list(
+                    x,
+                    StructConversion::Unnest,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )
+            }),
         );
 
         let robj_list_res = robj_vec_res
@@ -249,12 +269,23 @@ impl RPolarsDataFrame {
     }
 
     //this methods should only be used for benchmarking
-    pub fn to_list_unwind(&self, int64_conversion: &str) -> Robj {
+    pub fn to_list_unwind(
+        &self,
+        int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
+    ) -> Robj {
         let robj_vec_res: Result<Vec<Robj>, _> = collect_hinted_result(
             self.0.width(),
-            self.0
-                .iter()
-                .map(|x| pl_series_to_list(x, false, int64_conversion)),
+            self.0.iter().map(|x| {
+                pl_series_to_list(
+                    x,
+                    StructConversion::Unnest,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )
+            }),
         );
 
         let robj_list_res = robj_vec_res
@@ -268,15 +299,25 @@ impl RPolarsDataFrame {
         robj_list_res.unwrap()
     }
 
-    // to_list have this variant with set_structs = true at pl_series_to_list
-    // does not expose this arg in to_list as it is quite niche and might be deprecated later
-    pub fn to_list_tag_structs(&self, int64_conversion: &str) -> List {
+    // as to_list, but Struct columns become nested data.frames, see StructConversion::DataFrame
+    pub fn to_list_nested_structs(
+        &self,
+        int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
+    ) -> List {
         //convert DataFrame to Result of to R vectors, error if DataType is not supported
         let robj_vec_res: Result<Vec<Robj>, _> = collect_hinted_result(
             self.0.width(),
-            self.0
-                .iter()
-                .map(|x| pl_series_to_list(x, true, int64_conversion)),
+            self.0.iter().map(|x| {
+                pl_series_to_list(
+                    x,
+                    StructConversion::DataFrame,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )
+            }),
         );
 
         //rewrap Ok(Vec<Robj>) as R list
@@ -346,10 +387,19 @@ impl RPolarsDataFrame {
         }
     }
 
-    pub fn from_arrow_record_batches(rbr: Robj) -> Result<RPolarsDataFrame, String> {
-        Ok(RPolarsDataFrame(crate::arrow_interop::to_rust::to_rust_df(
-            rbr,
-        )?))
+    pub fn to_raw_ipc(&self, compression: Robj) -> RResult<Robj> {
+        crate::rbackground::serialize_dataframe_with_compression(
+            &mut self.0.clone(),
+            new_ipc_compression(compression)?,
+        )
+        .map(|bits| r!(Raw::from_bytes(&bits)))
+    }
+
+    pub fn from_raw_ipc(bits: Robj) -> RResult<RPolarsDataFrame> {
+        let bits = robj_to!(Raw, bits)?;
+        crate::rbackground::deserialize_dataframe(&bits)
+            .when("deserializing a DataFrame from raw IPC bytes")
+            .map(RPolarsDataFrame)
     }
 
     pub fn estimated_size(&self) -> f64 {
@@ -475,26 +525,41 @@ impl RPolarsDataFrame {
         float_precision: Robj,
         null_value: Robj,
         quote_style: Robj,
-    ) -> RResult<()> {
-        let path = robj_to!(str, path)?;
-        let f = std::fs::File::create(path)?;
-        pl::CsvWriter::new(f)
-            .include_bom(robj_to!(bool, include_bom)?)
-            .include_header(robj_to!(bool, include_header)?)
-            .with_separator(robj_to!(Utf8Byte, separator)?)
-            .with_line_terminator(robj_to!(String, line_terminator)?)
-            .with_quote_char(robj_to!(Utf8Byte, quote)?)
-            .with_batch_size(robj_to!(nonzero_usize, batch_size)?)
-            .with_datetime_format(robj_to!(Option, String, datetime_format)?)
-            .with_date_format(robj_to!(Option, String, date_format)?)
-            .with_time_format(robj_to!(Option, String, time_format)?)
-            .with_float_precision(robj_to!(Option, usize, float_precision)?)
-            .with_null_value(robj_to!(String, null_value)?)
-            .with_quote_style(robj_to!(QuoteStyle, quote_style)?)
-            .finish(&mut self.0.clone())
-            .map_err(polars_to_rpolars_err)
+        compression: Robj,
+    ) -> RResult<Robj> {
+        let path = robj_to!(Option, str, path)?;
+        let compression = new_text_compression(compression)?;
+        let include_bom = robj_to!(bool, include_bom)?;
+        let include_header = robj_to!(bool, include_header)?;
+        let separator = robj_to!(Utf8Byte, separator)?;
+        let line_terminator = robj_to!(String, line_terminator)?;
+        let quote = robj_to!(Utf8Byte, quote)?;
+        let batch_size = robj_to!(nonzero_usize, batch_size)?;
+        let datetime_format = robj_to!(Option, String, datetime_format)?;
+        let date_format = robj_to!(Option, String, date_format)?;
+        let time_format = robj_to!(Option, String, time_format)?;
+        let float_precision = robj_to!(Option, usize, float_precision)?;
+        let null_value = robj_to!(String, null_value)?;
+        let quote_style = robj_to!(QuoteStyle, quote_style)?;
+        write_text_output(path, compression, |f| {
+            pl::CsvWriter::new(f)
+                .include_bom(include_bom)
+                .include_header(include_header)
+                .with_separator(separator)
+                .with_line_terminator(line_terminator)
+                .with_quote_char(quote)
+                .with_batch_size(batch_size)
+                .with_datetime_format(datetime_format)
+                .with_date_format(date_format)
+                .with_time_format(time_format)
+                .with_float_precision(float_precision)
+                .with_null_value(null_value)
+                .with_quote_style(quote_style)
+                .finish(&mut self.0.clone())
+        })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn write_parquet(
         &self,
         path: Robj,
@@ -503,25 +568,66 @@ impl RPolarsDataFrame {
         statistics: Robj,
         row_group_size: Robj,
         data_pagesize_limit: Robj,
+        partition_by: Robj,
+        max_rows_per_file: Robj,
+        metadata: Robj,
     ) -> RResult<u64> {
         let path = robj_to!(str, path)?;
-        let f = std::fs::File::create(path)?;
-        pl::ParquetWriter::new(f)
-            .with_compression(new_parquet_compression(
-                compression_method,
-                compression_level,
-            )?)
-            .with_statistics(robj_to!(bool, statistics)?)
-            .with_row_group_size(robj_to!(Option, usize, row_group_size)?)
-            .with_data_page_size(robj_to!(Option, usize, data_pagesize_limit)?)
-            .set_parallel(true)
+        let metadata = crate::utils::robj_to_named_strings(metadata).bad_arg("metadata")?;
+        let options = pl::ParquetWriteOptions {
+            compression: new_parquet_compression(compression_method, compression_level)?,
+            statistics: robj_to!(bool, statistics)?,
+            row_group_size: robj_to!(Option, usize, row_group_size)?,
+            data_pagesize_limit: robj_to!(Option, usize, data_pagesize_limit)?,
+            maintain_order: true,
+        };
+        match robj_to!(Option, Vec, String, partition_by)? {
+            Some(by) => write_partitioned(
+                [Ok(self.0.clone())],
+                std::path::Path::new(path),
+                &by,
+                robj_to!(Option, usize, max_rows_per_file)?,
+                "parquet",
+                |f, schema| ParquetFileWriter::new(f, schema, &options, &metadata),
+            ),
+            None => {
+                let f = std::fs::File::create(path)?;
+                write_parquet_file(&mut self.0.clone(), f, &options, &metadata)
+            }
+        }
+        .map_err(polars_to_rpolars_err)
+    }
+
+    pub fn write_ipc(&self, file: Robj, compression: Robj, future: Robj) -> RResult<()> {
+        let f = std::fs::File::create(robj_to!(str, file)?)?;
+        pl::IpcWriter::new(f)
+            .with_compression(new_ipc_compression(compression)?)
+            .with_pl_flavor(robj_to!(bool, future)?)
             .finish(&mut self.0.clone())
             .map_err(polars_to_rpolars_err)
     }
 
-    pub fn write_json(&mut self, file: Robj, pretty: Robj, row_oriented: Robj) -> RResult<()> {
+    pub fn write_ipc_stream(&self, file: Robj, compression: Robj, future: Robj) -> RResult<()> {
         let f = std::fs::File::create(robj_to!(str, file)?)?;
-        match (robj_to!(bool, pretty)?, robj_to!(bool, row_oriented)?) {
+        pl::IpcStreamWriter::new(f)
+            .with_compression(new_ipc_compression(compression)?)
+            .with_pl_flavor(robj_to!(bool, future)?)
+            .finish(&mut self.0.clone())
+            .map_err(polars_to_rpolars_err)
+    }
+
+    pub fn write_json(
+        &mut self,
+        file: Robj,
+        pretty: Robj,
+        row_oriented: Robj,
+        compression: Robj,
+    ) -> RResult<Robj> {
+        let path = robj_to!(Option, str, file)?;
+        let compression = new_text_compression(compression)?;
+        let pretty = robj_to!(bool, pretty)?;
+        let row_oriented = robj_to!(bool, row_oriented)?;
+        write_text_output(path, compression, |f| match (pretty, row_oriented) {
             (_, true) => pl::JsonWriter::new(f)
                 .with_json_format(pl::JsonFormat::Json)
                 .finish(&mut self.0),
@@ -530,26 +636,51 @@ impl RPolarsDataFrame {
             (false, _) => {
                 serde_json::to_writer(f, &self.0).map_err(|e| pl::polars_err!(ComputeError: "{e}"))
             }
-        }
-        .map_err(polars_to_rpolars_err)
+        })
     }
 
-    pub fn write_ndjson(&mut self, file: Robj) -> RResult<()> {
+    pub fn write_ndjson(&mut self, file: Robj, compression: Robj) -> RResult<Robj> {
+        let path = robj_to!(Option, str, file)?;
+        let compression = new_text_compression(compression)?;
+        write_text_output(path, compression, |f| {
+            pl::JsonWriter::new(f)
+                .with_json_format(pl::JsonFormat::JsonLines)
+                .finish(&mut self.0)
+        })
+    }
+
+    pub fn write_avro(&mut self, file: Robj, compression: Robj, name: Robj) -> RResult<()> {
+        use polars::io::avro::AvroWriter;
         let f = std::fs::File::create(robj_to!(str, file)?)?;
-        pl::JsonWriter::new(f)
-            .with_json_format(pl::JsonFormat::JsonLines)
+        AvroWriter::new(f)
+            .with_compression(new_avro_compression(compression)?)
+            .with_name(robj_to!(String, name)?)
             .finish(&mut self.0)
             .map_err(polars_to_rpolars_err)
     }
 }
 
 impl RPolarsDataFrame {
-    pub fn to_list_result(&self, int64_conversion: &str) -> Result<Robj, pl::PolarsError> {
+    pub fn to_list_result(
+        &self,
+        struct_conversion: StructConversion,
+        int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
+    ) -> Result<Robj, pl::PolarsError> {
         //convert DataFrame to Result of to R vectors, error if DataType is not supported
         let robj_vec_res: Result<Vec<Robj>, _> = self
             .0
             .iter()
-            .map(|s| pl_series_to_list(s, true, int64_conversion))
+            .map(|s| {
+                pl_series_to_list(
+                    s,
+                    struct_conversion,
+                    int64_conversion,
+                    decimal_conversion,
+                    datetime_conversion,
+                )
+            })
             .collect();
 
         //rewrap Ok(Vec<Robj>) as R list
@@ -581,6 +712,7 @@ impl RPolarsVecDataFrame {
 
 extendr_module! {
     mod rdataframe;
+    use read_avro;
     use read_csv;
     use read_ipc;
     use read_ndjson;
@@ -0,0 +1,94 @@
+//read avro
+
+use crate::lazy::dataframe::RPolarsLazyFrame;
+use crate::rdataframe::RPolarsDataFrame;
+use crate::robj_to;
+use crate::rpolarserr::{polars_to_rpolars_err, RResult, WithRctx};
+
+use extendr_api::{extendr, prelude::*, Rinternals};
+use polars::io::avro::AvroReader;
+use polars::io::SerReader;
+use polars::prelude as pl;
+
+#[extendr]
+pub fn new_from_avro(
+    path: Robj,
+    n_rows: Robj,
+    columns: Robj,
+    projection: Robj,
+) -> RResult<RPolarsDataFrame> {
+    let path = robj_to!(String, path)?;
+    let f = std::fs::File::open(&path)
+        .bad_val(path)
+        .when("opening the avro file")?;
+
+    AvroReader::new(f)
+        .with_n_rows(robj_to!(Option, usize, n_rows)?)
+        .with_columns(robj_to!(Option, Vec, String, columns)?)
+        .with_projection(robj_to!(Option, Vec, usize, projection)?)
+        .finish()
+        .map_err(polars_to_rpolars_err)
+        .map(RPolarsDataFrame)
+}
+
+// A scan source that reads an avro file when the LazyFrame is collected. The projections and
+// slices pushed down by the optimizer are passed on to the reader.
+struct AvroScan {
+    path: String,
+}
+
+impl pl::AnonymousScan for AvroScan {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn scan(&self, args: pl::AnonymousScanArgs) -> pl::PolarsResult<pl::DataFrame> {
+        let columns = args
+            .with_columns
+            .map(|cols| cols.iter().map(|name| name.to_string()).collect());
+        AvroReader::new(std::fs::File::open(&self.path)?)
+            .with_n_rows(args.n_rows)
+            .with_columns(columns)
+            .finish()
+    }
+
+    fn allows_projection_pushdown(&self) -> bool {
+        true
+    }
+
+    fn allows_slice_pushdown(&self) -> bool {
+        true
+    }
+}
+
+#[extendr]
+pub fn new_lazy_from_avro(path: Robj, n_rows: Robj) -> RResult<RPolarsLazyFrame> {
+    let path = robj_to!(String, path)?;
+    let n_rows = robj_to!(Option, usize, n_rows)?;
+    let f = std::fs::File::open(&path)
+        .bad_val(path.clone())
+        .when("opening the avro file")?;
+    let schema = AvroReader::new(f)
+        .schema()
+        .map_err(polars_to_rpolars_err)
+        .bad_val(path.clone())
+        .when("reading the schema of the avro file")?;
+
+    let args = pl::ScanArgsAnonymous {
+        schema: Some(std::sync::Arc::new(schema)),
+        name: "AVRO SCAN",
+        ..Default::default()
+    };
+    let mut lf = pl::LazyFrame::anonymous_scan(std::sync::Arc::new(AvroScan { path }), args)
+        .map_err(polars_to_rpolars_err)?;
+    if let Some(n) = n_rows {
+        lf = lf.limit(n as pl::IdxSize);
+    }
+    Ok(RPolarsLazyFrame(lf))
+}
+
+extendr_module! {
+    mod read_avro;
+    fn new_from_avro;
+    fn new_lazy_from_avro;
+}
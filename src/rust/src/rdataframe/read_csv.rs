@@ -3,14 +3,19 @@
 use crate::rdatatype::RPolarsDataTypeVector;
 
 use crate::lazy::dataframe::RPolarsLazyFrame;
+use crate::rdataframe::compression::{decompressed, scan_maybe_compressed};
+use crate::rdataframe::RPolarsDataFrame;
 use crate::robj_to;
 use crate::rpolarserr::*;
+use polars::io::csv::read_impl::{OwnedBatchedCsvReader, OwnedBatchedCsvReaderMmap};
+use polars::io::mmap::MmapBytesReader;
 use polars::io::RowIndex;
 
 //use crate::utils::wrappers::*;
 use crate::utils::wrappers::{null_to_opt, Wrap};
 use extendr_api::{extendr, prelude::*, Rinternals};
-use polars::prelude as pl;
+use polars::prelude::{self as pl, SerReader};
+use std::path::PathBuf;
 use std::result::Result;
 
 //see param, null_values
@@ -44,6 +49,39 @@ impl From<Wrap<Nullable<&RPolarsRNullValues>>> for Option<pl::NullValues> {
     }
 }
 
+fn new_csv_encoding(encoding: &str) -> RResult<pl::CsvEncoding> {
+    match encoding {
+        "utf8" => Ok(pl::CsvEncoding::Utf8),
+        "utf8-lossy" => Ok(pl::CsvEncoding::LossyUtf8),
+        _ => rerr().bad_val(format!("encoding choice: '{}' is not supported", encoding)),
+    }
+}
+
+//construct optional Schema parameter for overwrite_dtype
+fn dtypes_to_schema(dtypes: Nullable<&RPolarsDataTypeVector>) -> RResult<Option<pl::Schema>> {
+    null_to_opt(dtypes)
+        .map(|some_od| {
+            some_od
+                .0
+                .iter()
+                .map(|(name, dtype)| match name {
+                    Some(sname) => Ok(pl::Field::new(sname, dtype.clone())),
+                    None => rerr().plain("every dtype must be named").bad_arg("dtypes"),
+                })
+                .collect::<RResult<pl::Schema>>()
+        })
+        .transpose()
+}
+
+// data held in memory as a raw vector
+pub fn robj_to_bytes(source: &Robj) -> RResult<&[u8]> {
+    source
+        .as_raw_slice()
+        .ok_or(RPolarsErr::new())
+        .bad_robj(source)
+        .mistyped("raw vector")
+}
+
 #[allow(clippy::too_many_arguments)]
 #[extendr]
 pub fn new_from_csv(
@@ -70,48 +108,114 @@ pub fn new_from_csv(
     eol_char: Robj,
     raise_if_empty: Robj,
     truncate_ragged_lines: Robj,
+    tmp_dir: Robj,
 ) -> RResult<RPolarsLazyFrame> {
     let offset = robj_to!(Option, u32, row_count_offset)?.unwrap_or(0);
     let opt_rowcount =
         robj_to!(Option, String, row_count_name)?.map(|name| RowIndex { name, offset });
 
-    let vec_pathbuf = robj_to!(Vec, PathBuf, path)?;
-    let linereader = match vec_pathbuf.len() {
-        2.. => Ok(pl::LazyCsvReader::new_paths(vec_pathbuf.into())),
-        1 => Ok(pl::LazyCsvReader::new(&vec_pathbuf[0])),
-        _ => rerr().plain("path cannot have zero length").bad_arg("path"),
-    }?;
-    //construct encoding parameter
-    let encoding = match encoding {
-        "utf8" => Ok(pl::CsvEncoding::Utf8),
-        "utf8-lossy" => Ok(pl::CsvEncoding::LossyUtf8),
-        _ => rerr().bad_val(format!("encoding choice: '{}' is not supported", encoding)),
-    }?;
-
-    //construct optional Schema parameter for overwrite_dtype
-    let dtv = null_to_opt(dtypes).cloned();
-    let schema = dtv.map(|some_od| {
-        let fields = some_od.0.iter().map(|(name, dtype)| {
-            if let Some(sname) = name {
-                pl::Field::new(sname, dtype.clone())
-            } else {
-                todo!("missing column name for  dtype not implemented");
-            }
-        });
-        pl::Schema::from_iter(fields)
-    });
-
-    linereader
-        .with_infer_schema_length(robj_to!(Option, usize, infer_schema_length)?)
+    let paths = robj_to!(Vec, String, path)?;
+    if paths.is_empty() {
+        return rerr().plain("path cannot have zero length").bad_arg("path");
+    }
+    let encoding = new_csv_encoding(encoding)?;
+    let schema = dtypes_to_schema(dtypes)?;
+    let infer_schema_length = robj_to!(Option, usize, infer_schema_length)?;
+    let separator = robj_to!(Utf8Byte, separator)?;
+    let has_header = robj_to!(bool, has_header)?;
+    let ignore_errors = robj_to!(bool, ignore_errors)?;
+    let skip_rows = robj_to!(usize, skip_rows)?;
+    let n_rows = robj_to!(Option, usize, n_rows)?;
+    let cache = robj_to!(bool, cache)?;
+    let low_memory = robj_to!(bool, low_memory)?;
+    let comment_prefix = robj_to!(Option, String, comment_prefix)?;
+    let quote_char = robj_to!(Option, Utf8Byte, quote_char)?;
+    let eol_char = robj_to!(Utf8Byte, eol_char)?;
+    let rechunk = robj_to!(bool, rechunk)?;
+    let skip_rows_after_header = robj_to!(usize, skip_rows_after_header)?;
+    let try_parse_dates = robj_to!(bool, try_parse_dates)?;
+    let null_values: Option<pl::NullValues> = Wrap(null_values).into();
+    let tmp_dir = PathBuf::from(robj_to!(String, tmp_dir)?);
+    let truncate_ragged_lines = robj_to!(bool, truncate_ragged_lines)?;
+    let raise_if_empty = robj_to!(bool, raise_if_empty)?;
+
+    let scan = move |paths: Vec<PathBuf>| {
+        let linereader = match paths.len() {
+            1 => pl::LazyCsvReader::new(&paths[0]),
+            _ => pl::LazyCsvReader::new_paths(paths.into()),
+        };
+        linereader
+            .with_infer_schema_length(infer_schema_length)
+            .with_separator(separator)
+            .has_header(has_header)
+            .with_ignore_errors(ignore_errors)
+            .with_skip_rows(skip_rows)
+            .with_n_rows(n_rows)
+            .with_cache(cache)
+            .with_dtype_overwrite(schema.as_ref())
+            .low_memory(low_memory)
+            .with_comment_prefix(comment_prefix.as_deref())
+            .with_quote_char(quote_char)
+            .with_end_of_line_char(eol_char)
+            .with_rechunk(rechunk)
+            .with_skip_rows_after_header(skip_rows_after_header)
+            .with_encoding(encoding)
+            .with_try_parse_dates(try_parse_dates)
+            .with_null_values(null_values.clone())
+            // .with_missing_is_null(!robj_to!(bool, missing_utf8_is_empty_string)?)
+            .with_row_index(opt_rowcount.clone())
+            .truncate_ragged_lines(truncate_ragged_lines)
+            .raise_if_empty(raise_if_empty)
+            .finish()
+    };
+    // the header and the skipped rows come on top of the rows the schema is inferred from
+    let schema_lines = infer_schema_length.map(|n| n + skip_rows + skip_rows_after_header + 1);
+    scan_maybe_compressed(paths, tmp_dir, schema_lines, scan).map(RPolarsLazyFrame)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[extendr]
+pub fn read_csv_from_bytes(
+    source: Robj,
+    has_header: Robj,
+    separator: Robj,
+    comment_prefix: Robj,
+    quote_char: Robj,
+    skip_rows: Robj,
+    dtypes: Nullable<&RPolarsDataTypeVector>,
+    null_values: Nullable<&RPolarsRNullValues>,
+    ignore_errors: Robj,
+    infer_schema_length: Robj,
+    n_rows: Robj,
+    encoding: &str,
+    low_memory: Robj,
+    rechunk: Robj,
+    skip_rows_after_header: Robj,
+    row_count_name: Robj,
+    row_count_offset: Robj,
+    try_parse_dates: Robj,
+    eol_char: Robj,
+    raise_if_empty: Robj,
+    truncate_ragged_lines: Robj,
+) -> RResult<RPolarsDataFrame> {
+    let offset = robj_to!(Option, u32, row_count_offset)?.unwrap_or(0);
+    let opt_rowcount =
+        robj_to!(Option, String, row_count_name)?.map(|name| RowIndex { name, offset });
+    let encoding = new_csv_encoding(encoding)?;
+    let schema = dtypes_to_schema(dtypes)?.map(std::sync::Arc::new);
+    let comment_prefix = robj_to!(Option, String, comment_prefix)?;
+    let bytes = robj_to_bytes(&source).bad_arg("source")?;
+
+    pl::CsvReader::new(std::io::Cursor::new(bytes))
+        .infer_schema(robj_to!(Option, usize, infer_schema_length)?)
         .with_separator(robj_to!(Utf8Byte, separator)?)
         .has_header(robj_to!(bool, has_header)?)
         .with_ignore_errors(robj_to!(bool, ignore_errors)?)
         .with_skip_rows(robj_to!(usize, skip_rows)?)
         .with_n_rows(robj_to!(Option, usize, n_rows)?)
-        .with_cache(robj_to!(bool, cache)?)
-        .with_dtype_overwrite(schema.as_ref())
+        .with_dtypes(schema)
         .low_memory(robj_to!(bool, low_memory)?)
-        .with_comment_prefix(robj_to!(Option, str, comment_prefix)?)
+        .with_comment_prefix(comment_prefix.as_deref())
         .with_quote_char(robj_to!(Option, Utf8Byte, quote_char)?)
         .with_end_of_line_char(robj_to!(Utf8Byte, eol_char)?)
         .with_rechunk(robj_to!(bool, rechunk)?)
@@ -119,17 +223,126 @@ pub fn new_from_csv(
         .with_encoding(encoding)
         .with_try_parse_dates(robj_to!(bool, try_parse_dates)?)
         .with_null_values(Wrap(null_values).into())
-        // .with_missing_is_null(!robj_to!(bool, missing_utf8_is_empty_string)?)
         .with_row_index(opt_rowcount)
         .truncate_ragged_lines(robj_to!(bool, truncate_ragged_lines)?)
         .raise_if_empty(robj_to!(bool, raise_if_empty)?)
         .finish()
         .map_err(polars_to_rpolars_err)
-        .map(RPolarsLazyFrame)
+        .map(RPolarsDataFrame)
+}
+
+enum BatchedReader {
+    MMap(OwnedBatchedCsvReaderMmap),
+    Read(OwnedBatchedCsvReader),
+}
+
+// reads a CSV file a few batches at a time, see pl$read_csv_batched(). A compressed file is
+// decompressed into a temporary file, which is removed with the reader.
+pub struct RPolarsBatchedCsvReader {
+    reader: BatchedReader,
+    _decompressed: Option<crate::utils::TempPath>,
+}
+
+#[extendr]
+impl RPolarsBatchedCsvReader {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        path: Robj,
+        has_header: Robj,
+        separator: Robj,
+        comment_prefix: Robj,
+        quote_char: Robj,
+        skip_rows: Robj,
+        dtypes: Nullable<&RPolarsDataTypeVector>,
+        null_values: Nullable<&RPolarsRNullValues>,
+        ignore_errors: Robj,
+        infer_schema_length: Robj,
+        n_rows: Robj,
+        encoding: &str,
+        low_memory: Robj,
+        skip_rows_after_header: Robj,
+        row_count_name: Robj,
+        row_count_offset: Robj,
+        try_parse_dates: Robj,
+        eol_char: Robj,
+        raise_if_empty: Robj,
+        truncate_ragged_lines: Robj,
+        batch_size: Robj,
+        tmp_dir: Robj,
+    ) -> RResult<Self> {
+        let offset = robj_to!(Option, u32, row_count_offset)?.unwrap_or(0);
+        let opt_rowcount =
+            robj_to!(Option, String, row_count_name)?.map(|name| RowIndex { name, offset });
+        let encoding = new_csv_encoding(encoding)?;
+        let schema = dtypes_to_schema(dtypes)?.map(std::sync::Arc::new);
+        let comment_prefix = robj_to!(Option, String, comment_prefix)?;
+        let low_memory = robj_to!(bool, low_memory)?;
+        let path = robj_to!(String, path)?;
+        let tmp_dir = PathBuf::from(robj_to!(String, tmp_dir)?);
+        let tmp = decompressed(std::path::Path::new(&path), &tmp_dir, None)
+            .map_err(polars_to_rpolars_err)
+            .bad_val(path.clone())?;
+        let file = std::fs::File::open(
+            tmp.as_ref()
+                .map_or(std::path::Path::new(&path), |tmp| tmp.path()),
+        )
+        .bad_val(path)
+        .when("opening the CSV file")?;
+
+        let reader = pl::CsvReader::new(Box::new(file) as Box<dyn MmapBytesReader>)
+            .infer_schema(robj_to!(Option, usize, infer_schema_length)?)
+            .with_separator(robj_to!(Utf8Byte, separator)?)
+            .has_header(robj_to!(bool, has_header)?)
+            .with_ignore_errors(robj_to!(bool, ignore_errors)?)
+            .with_skip_rows(robj_to!(usize, skip_rows)?)
+            .with_n_rows(robj_to!(Option, usize, n_rows)?)
+            .with_dtypes(schema)
+            .low_memory(low_memory)
+            .with_comment_prefix(comment_prefix.as_deref())
+            .with_quote_char(robj_to!(Option, Utf8Byte, quote_char)?)
+            .with_end_of_line_char(robj_to!(Utf8Byte, eol_char)?)
+            .with_rechunk(false)
+            .with_chunk_size(robj_to!(usize, batch_size)?)
+            .with_skip_rows_after_header(robj_to!(usize, skip_rows_after_header)?)
+            .with_encoding(encoding)
+            .with_try_parse_dates(robj_to!(bool, try_parse_dates)?)
+            .with_null_values(Wrap(null_values).into())
+            .with_row_index(opt_rowcount)
+            .truncate_ragged_lines(robj_to!(bool, truncate_ragged_lines)?)
+            .raise_if_empty(robj_to!(bool, raise_if_empty)?);
+
+        // the schema is inferred from the file, `dtypes` only overwrites some columns
+        let reader = if low_memory {
+            reader.batched_read(None).map(BatchedReader::Read)
+        } else {
+            reader.batched_mmap(None).map(BatchedReader::MMap)
+        }
+        .map_err(polars_to_rpolars_err)?;
+        Ok(RPolarsBatchedCsvReader {
+            reader,
+            _decompressed: tmp,
+        })
+    }
+
+    // a list of at most `n` DataFrames, or NULL once the file is exhausted
+    pub fn next_batches(&mut self, n: Robj) -> RResult<Robj> {
+        let n = robj_to!(usize, n)?;
+        let batches = match &mut self.reader {
+            BatchedReader::MMap(reader) => reader.next_batches(n),
+            BatchedReader::Read(reader) => reader.next_batches(n),
+        }
+        .map_err(polars_to_rpolars_err)?;
+        Ok(match batches {
+            Some(dfs) => List::from_values(dfs.into_iter().map(RPolarsDataFrame)).into(),
+            None => NULL.into(),
+        })
+    }
 }
 
 extendr_module! {
     mod read_csv;
     fn new_from_csv;
+    fn read_csv_from_bytes;
     impl RPolarsRNullValues;
+    impl RPolarsBatchedCsvReader;
 }
@@ -1,10 +1,14 @@
 use crate::lazy::dataframe::RPolarsLazyFrame as RLazyFrame;
+use crate::rdataframe::RPolarsDataFrame as RDF;
 use crate::robj_to;
-use crate::rpolarserr::RResult;
+use crate::rpolarserr::{polars_to_rpolars_err, rerr, RResult, WithRctx};
+use crate::utils::scan_files;
 use extendr_api::prelude::*;
 use polars::io::RowIndex;
-use polars::prelude::{LazyFrame, ScanArgsIpc};
+use polars::io::SerReader;
+use polars::prelude::{IpcStreamReader, LazyFrame, ScanArgsIpc};
 
+#[allow(clippy::too_many_arguments)]
 #[extendr]
 pub fn import_arrow_ipc(
     path: Robj,
@@ -14,8 +18,11 @@ pub fn import_arrow_ipc(
     row_name: Robj,
     row_index: Robj,
     memmap: Robj,
+    include_file_paths: Robj,
 ) -> RResult<RLazyFrame> {
-    let args = ScanArgsIpc {
+    let paths = robj_to!(Vec, String, path)?;
+    let include_file_paths = robj_to!(Option, String, include_file_paths)?;
+    let mut args = ScanArgsIpc {
         n_rows: robj_to!(Option, usize, n_rows)?,
         cache: robj_to!(bool, cache)?,
         rechunk: robj_to!(bool, rechunk)?,
@@ -24,12 +31,88 @@ pub fn import_arrow_ipc(
             .transpose()?,
         memmap: robj_to!(bool, memmap)?,
     };
-    let lf = LazyFrame::scan_ipc(robj_to!(String, path)?, args)
-        .map_err(crate::rpolarserr::polars_to_rpolars_err)?;
-    Ok(RLazyFrame(lf))
+
+    let Some(first_path) = paths.first() else {
+        return rerr()
+            .plain("at least one path is required")
+            .bad_arg("path");
+    };
+    if paths.len() == 1 && include_file_paths.is_none() {
+        return LazyFrame::scan_ipc(first_path, args)
+            .map_err(polars_to_rpolars_err)
+            .map(RLazyFrame);
+    }
+
+    let n_rows = args.n_rows.take();
+    let row_index = args.row_index.take();
+    let rechunk = args.rechunk;
+    scan_files(
+        paths,
+        include_file_paths,
+        n_rows,
+        row_index,
+        rechunk,
+        |path| LazyFrame::scan_ipc(path, args.clone()),
+    )
+    .map(RLazyFrame)
+}
+
+// options shared by all sources of an IPC stream
+struct IpcStreamArgs {
+    n_rows: Option<usize>,
+    columns: Option<Vec<String>>,
+    projection: Option<Vec<usize>>,
+    row_index: Option<RowIndex>,
+    rechunk: bool,
+}
+
+fn read_ipc_stream<R: std::io::Read>(reader: R, args: IpcStreamArgs) -> RResult<RDF> {
+    IpcStreamReader::new(reader)
+        .with_n_rows(args.n_rows)
+        .with_columns(args.columns)
+        .with_projection(args.projection)
+        .with_row_index(args.row_index)
+        .set_rechunk(args.rechunk)
+        .finish()
+        .map_err(polars_to_rpolars_err)
+        .map(RDF)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[extendr]
+pub fn import_arrow_ipc_stream(
+    source: Robj,
+    n_rows: Robj,
+    columns: Robj,
+    projection: Robj,
+    rechunk: Robj,
+    row_name: Robj,
+    row_index: Robj,
+) -> RResult<RDF> {
+    let args = IpcStreamArgs {
+        n_rows: robj_to!(Option, usize, n_rows)?,
+        columns: robj_to!(Option, Vec, String, columns)?,
+        projection: robj_to!(Option, Vec, usize, projection)?,
+        row_index: robj_to!(Option, String, row_name)?
+            .map(|name| robj_to!(u32, row_index).map(|offset| RowIndex { name, offset }))
+            .transpose()?,
+        rechunk: robj_to!(bool, rechunk)?,
+    };
+
+    // a raw vector holds the stream in memory, anything else must be a path
+    if let Some(bits) = source.as_raw_slice() {
+        read_ipc_stream(std::io::Cursor::new(bits), args)
+    } else {
+        let path = robj_to!(String, source)?;
+        let f = std::fs::File::open(&path)
+            .bad_val(path)
+            .when("opening the IPC stream file")?;
+        read_ipc_stream(std::io::BufReader::new(f), args)
+    }
 }
 
 extendr_module! {
     mod read_ipc;
     fn import_arrow_ipc;
+    fn import_arrow_ipc_stream;
 }
@@ -1,14 +1,20 @@
 //read ndjson
 
 use crate::lazy::dataframe::RPolarsLazyFrame;
+use crate::rdataframe::compression::scan_maybe_compressed;
+use crate::rdataframe::read_csv::robj_to_bytes;
+use crate::rdataframe::RPolarsDataFrame;
+use crate::rdatatype::RPolarsDataTypeVector;
 use crate::robj_to;
 use crate::rpolarserr::*;
+use crate::utils::wrappers::null_to_opt;
 use polars::io::RowIndex;
 
 //use crate::utils::wrappers::*;
 use extendr_api::{extendr, prelude::*, Rinternals};
-use polars::prelude as pl;
 use polars::prelude::LazyFileListReader;
+use polars::prelude::{self as pl, SerReader};
+use std::path::PathBuf;
 use std::result::Result;
 
 #[allow(clippy::too_many_arguments)]
@@ -23,32 +29,150 @@ pub fn new_from_ndjson(
     row_index_name: Robj,
     row_index_offset: Robj,
     ignore_errors: Robj,
+    tmp_dir: Robj,
 ) -> RResult<RPolarsLazyFrame> {
     let offset = robj_to!(Option, u32, row_index_offset)?.unwrap_or(0);
     let opt_rowindex =
         robj_to!(Option, String, row_index_name)?.map(|name| RowIndex { name, offset });
 
-    let vec_pathbuf = robj_to!(Vec, PathBuf, path)?;
-    let linereader = match vec_pathbuf.len() {
-        2.. => Ok(pl::LazyJsonLineReader::new_paths(vec_pathbuf.into())),
-        1 => Ok(pl::LazyJsonLineReader::new(&vec_pathbuf[0])),
-        _ => rerr().plain("path cannot have zero length").bad_arg("path"),
-    }?;
+    let paths = robj_to!(Vec, String, path)?;
+    if paths.is_empty() {
+        return rerr().plain("path cannot have zero length").bad_arg("path");
+    }
+    let infer_schema_length = robj_to!(Option, usize, infer_schema_length)?;
+    let batch_size = robj_to!(Option, nonzero_usize, batch_size)?;
+    let n_rows = robj_to!(Option, usize, n_rows)?;
+    let low_memory = robj_to!(bool, low_memory)?;
+    let rechunk = robj_to!(bool, rechunk)?;
+    let ignore_errors = robj_to!(bool, ignore_errors)?;
+    let tmp_dir = PathBuf::from(robj_to!(String, tmp_dir)?);
 
-    linereader
-        .with_infer_schema_length(robj_to!(Option, usize, infer_schema_length)?)
-        .with_batch_size(robj_to!(Option, nonzero_usize, batch_size)?)
+    let scan = move |paths: Vec<PathBuf>| {
+        let linereader = match paths.len() {
+            1 => pl::LazyJsonLineReader::new(&paths[0]),
+            _ => pl::LazyJsonLineReader::new_paths(paths.into()),
+        };
+        linereader
+            .with_infer_schema_length(infer_schema_length)
+            .with_batch_size(batch_size)
+            .with_n_rows(n_rows)
+            .low_memory(low_memory)
+            .with_row_index(opt_rowindex.clone())
+            .with_rechunk(rechunk)
+            .with_ignore_errors(ignore_errors)
+            .finish()
+    };
+    scan_maybe_compressed(paths, tmp_dir, infer_schema_length, scan).map(RPolarsLazyFrame)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[extendr]
+pub fn read_ndjson_from_bytes(
+    source: Robj,
+    infer_schema_length: Robj,
+    batch_size: Robj,
+    n_rows: Robj,
+    low_memory: Robj,
+    rechunk: Robj,
+    row_index_name: Robj,
+    row_index_offset: Robj,
+    ignore_errors: Robj,
+) -> RResult<RPolarsDataFrame> {
+    let offset = robj_to!(Option, u32, row_index_offset)?.unwrap_or(0);
+    let row_index_name = robj_to!(Option, String, row_index_name)?;
+    let bytes = robj_to_bytes(&source).bad_arg("source")?;
+
+    let df = pl::JsonLineReader::new(std::io::Cursor::new(bytes))
+        .infer_schema_len(robj_to!(Option, usize, infer_schema_length)?)
+        .with_chunk_size(robj_to!(Option, nonzero_usize, batch_size)?)
         .with_n_rows(robj_to!(Option, usize, n_rows)?)
         .low_memory(robj_to!(bool, low_memory)?)
-        .with_row_index(opt_rowindex)
         .with_rechunk(robj_to!(bool, rechunk)?)
         .with_ignore_errors(robj_to!(bool, ignore_errors)?)
         .finish()
-        .map_err(polars_to_rpolars_err)
-        .map(RPolarsLazyFrame)
+        .map_err(polars_to_rpolars_err)?;
+    match row_index_name {
+        Some(name) => df.with_row_index(&name, Some(offset)),
+        None => Ok(df),
+    }
+    .map_err(polars_to_rpolars_err)
+    .map(RPolarsDataFrame)
+}
+
+// Cast the columns of a DataFrame read from the column-oriented layout. `schema` also selects
+// the columns and sets their order, as it does for the row-oriented layout.
+fn apply_json_schemas(
+    df: pl::DataFrame,
+    schema: Option<&pl::Schema>,
+    schema_overrides: Option<&pl::Schema>,
+) -> pl::PolarsResult<pl::DataFrame> {
+    let cast = |schema: &pl::Schema| -> Vec<pl::Expr> {
+        schema
+            .iter()
+            .map(|(name, dtype)| pl::col(name).cast(dtype.clone()))
+            .collect()
+    };
+    let mut lf = pl::IntoLazy::lazy(df);
+    if let Some(schema) = schema {
+        lf = lf.select(cast(schema));
+    }
+    if let Some(schema_overrides) = schema_overrides {
+        lf = lf.with_columns(cast(schema_overrides));
+    }
+    lf.collect()
+}
+
+// Either a JSON array of objects, e.g. `[{"a": 1}, {"a": 2}]`, or the column-oriented layout
+// written by `<DataFrame>$write_json()`, `{"columns": [{"name": "a", "datatype": ...}]}`,
+// which keeps the data types.
+#[extendr]
+pub fn read_json(
+    source: Robj,
+    schema: Nullable<&RPolarsDataTypeVector>,
+    schema_overrides: Nullable<&RPolarsDataTypeVector>,
+    infer_schema_length: Robj,
+) -> RResult<RPolarsDataFrame> {
+    let schema = null_to_opt(schema).map(|dtv| dtv.dtv_to_schema());
+    let schema_overrides = null_to_opt(schema_overrides).map(|dtv| dtv.dtv_to_schema());
+    let infer_schema_length = robj_to!(Option, usize, infer_schema_length)?;
+    let read = |bytes: &[u8]| {
+        let column_oriented = bytes
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|b| *b == b'{');
+        if column_oriented {
+            return serde_json::from_slice::<pl::DataFrame>(bytes)
+                .map_err(|e| pl::polars_err!(ComputeError: "{e}"))
+                .and_then(|df| apply_json_schemas(df, schema.as_ref(), schema_overrides.as_ref()));
+        }
+        let mut reader = pl::JsonReader::new(std::io::Cursor::new(bytes))
+            .with_json_format(pl::JsonFormat::Json)
+            .infer_schema_len(infer_schema_length);
+        if let Some(schema) = &schema {
+            reader = reader.with_schema(std::sync::Arc::new(schema.clone()));
+        }
+        if let Some(schema_overrides) = &schema_overrides {
+            reader = reader.with_schema_overwrite(schema_overrides);
+        }
+        reader.finish()
+    };
+
+    // a raw vector holds the JSON in memory, anything else must be a path
+    let df = if let Some(bytes) = source.as_raw_slice() {
+        read(bytes)
+    } else {
+        let path = robj_to!(String, source)?;
+        let bytes = std::fs::read(&path)
+            .bad_val(path)
+            .when("reading the JSON file")?;
+        read(&bytes)
+    };
+    df.map_err(polars_to_rpolars_err).map(RPolarsDataFrame)
 }
 
 extendr_module! {
     mod read_ndjson;
     fn new_from_ndjson;
+    fn read_ndjson_from_bytes;
+    fn read_json;
 }
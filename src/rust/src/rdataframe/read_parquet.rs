@@ -1,11 +1,136 @@
 use crate::lazy::dataframe::RPolarsLazyFrame;
+use crate::rdataframe::RPolarsDataFrame;
+use crate::rdatatype::{
+    check_same_scheme, new_cloud_options, RPolarsDataType, RPolarsDataTypeVector,
+};
 use crate::robj_to;
-use crate::rpolarserr::{polars_to_rpolars_err, RResult};
+use crate::rpolarserr::{polars_to_rpolars_err, rerr, RResult, WithRctx};
+use crate::utils::wrappers::null_to_opt;
+use crate::utils::{check_no_cloud_glob, scan_files};
 
-use extendr_api::Rinternals;
-use extendr_api::{extendr, extendr_module, Robj};
+use extendr_api::{extendr, prelude::*, Rinternals};
+use polars::export::chrono::{NaiveDate, NaiveDateTime};
 use polars::io::RowIndex;
 use polars::prelude::{self as pl};
+
+// schemas applied to each scanned file, so that all files end up with the same columns
+struct ScanSchemas {
+    schema: Option<pl::Schema>,
+    hive_schema: Option<pl::Schema>,
+    try_parse_hive_dates: bool,
+    hive_partitioning: bool,
+}
+
+impl ScanSchemas {
+    fn is_empty(&self) -> bool {
+        self.schema.is_none() && self.hive_schema.is_none() && !self.try_parse_hive_dates
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// `key=value` segments of a hive partitioned path
+fn hive_key_values(path: &str) -> Vec<(String, String)> {
+    path.split(['/', '\\'])
+        .filter_map(|segment| segment.split_once('='))
+        .map(|(key, value)| (key.to_string(), percent_decode(value)))
+        .collect()
+}
+
+// the value of a hive partition as written in the path, before any type inference
+fn hive_literal(value: &str) -> pl::Expr {
+    if value == "__HIVE_DEFAULT_PARTITION__" {
+        pl::lit(pl::NULL)
+    } else {
+        pl::lit(value)
+    }
+}
+
+fn parse_hive_date(value: &str) -> Option<pl::Expr> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(pl::lit(date));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .map(pl::lit)
+}
+
+// Columns of `schema` come first, in that order, and are filled with nulls if missing in the
+// file. The remaining columns, e.g. hive partitions, follow. Hive partitions with a given
+// type are cast from their value in the path, as the type inferred by the scanner may have
+// lost information, e.g. "007" read as 7.
+fn apply_schemas(
+    lf: pl::LazyFrame,
+    path: &str,
+    schemas: &ScanSchemas,
+) -> pl::PolarsResult<pl::LazyFrame> {
+    let file_schema = lf.schema()?;
+    let hive_values = if schemas.hive_partitioning {
+        hive_key_values(path)
+    } else {
+        Vec::new()
+    };
+    let hive_value = |name: &str| {
+        hive_values
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let typed_column = |name: &str, dtype: &pl::DataType| match hive_value(name) {
+        Some(value) => hive_literal(value).strict_cast(dtype.clone()).alias(name),
+        None => pl::col(name).cast(dtype.clone()),
+    };
+
+    let mut exprs = Vec::with_capacity(file_schema.len());
+    if let Some(schema) = &schemas.schema {
+        for (name, dtype) in schema.iter() {
+            exprs.push(if file_schema.contains(name) {
+                typed_column(name, dtype)
+            } else {
+                pl::lit(pl::NULL).cast(dtype.clone()).alias(name)
+            });
+        }
+    }
+
+    for name in file_schema.iter_names() {
+        if schemas.schema.as_ref().is_some_and(|s| s.contains(name)) {
+            continue;
+        }
+        let hive_dtype = schemas.hive_schema.as_ref().and_then(|s| s.get(name));
+        let hive_date = hive_value(name)
+            .filter(|_| schemas.try_parse_hive_dates)
+            .and_then(parse_hive_date);
+        exprs.push(match (hive_dtype, hive_date) {
+            (Some(dtype), _) => typed_column(name, dtype),
+            (None, Some(date)) => date.alias(name),
+            (None, None) => pl::col(name),
+        });
+    }
+    Ok(lf.select(exprs))
+}
+
 #[allow(clippy::too_many_arguments)]
 #[extendr]
 pub fn new_from_parquet(
@@ -16,32 +141,176 @@ pub fn new_from_parquet(
     rechunk: Robj,
     row_name: Robj,
     row_index: Robj,
-    //storage_options: Robj, // not supported yet, add provide features e.g. aws
+    storage_options: Robj,
     use_statistics: Robj,
     low_memory: Robj,
     hive_partitioning: Robj,
-    //retries: Robj // not supported yet, with CloudOptions
+    retries: Robj,
+    include_file_paths: Robj,
+    schema: Nullable<&RPolarsDataTypeVector>,
+    hive_schema: Nullable<&RPolarsDataTypeVector>,
+    try_parse_hive_dates: Robj,
 ) -> RResult<RPolarsLazyFrame> {
+    let paths = robj_to!(Vec, String, path)?;
+    let Some(first_path) = paths.first() else {
+        return rerr()
+            .plain("at least one path is required")
+            .bad_arg("path");
+    };
+    check_same_scheme(&paths)?;
+    let include_file_paths = robj_to!(Option, String, include_file_paths)?;
+    let hive_partitioning = robj_to!(bool, hive_partitioning)?;
+    let schemas = ScanSchemas {
+        schema: null_to_opt(schema).map(|dtv| dtv.dtv_to_schema()),
+        hive_schema: null_to_opt(hive_schema).map(|dtv| dtv.dtv_to_schema()),
+        try_parse_hive_dates: robj_to!(bool, try_parse_hive_dates)?,
+        hive_partitioning,
+    };
     let offset = robj_to!(Option, u32, row_index)?.unwrap_or(0);
     let opt_row_index = robj_to!(Option, String, row_name)?.map(|name| RowIndex { name, offset });
-    let args = pl::ScanArgsParquet {
+    let mut args = pl::ScanArgsParquet {
         n_rows: robj_to!(Option, usize, n_rows)?,
         cache: robj_to!(bool, cache)?,
         parallel: robj_to!(ParallelStrategy, parallel)?,
         rechunk: robj_to!(bool, rechunk)?,
         row_index: opt_row_index,
         low_memory: robj_to!(bool, low_memory)?,
-        cloud_options: None,
+        cloud_options: new_cloud_options(first_path, storage_options, retries)?,
         use_statistics: robj_to!(bool, use_statistics)?,
-        hive_partitioning: robj_to!(bool, hive_partitioning)?,
+        hive_partitioning,
     };
 
-    pl::LazyFrame::scan_parquet(robj_to!(String, path)?, args)
-        .map_err(polars_to_rpolars_err)
-        .map(RPolarsLazyFrame)
+    // the hive partitions of each file would be parsed from the pattern instead of its path
+    if !schemas.is_empty() {
+        check_no_cloud_glob(&paths, "`schema`, `hive_schema` or `try_parse_hive_dates`")?;
+    }
+
+    if paths.len() == 1 && include_file_paths.is_none() && schemas.is_empty() {
+        return pl::LazyFrame::scan_parquet(first_path, args)
+            .map_err(polars_to_rpolars_err)
+            .map(RPolarsLazyFrame);
+    }
+
+    let n_rows = args.n_rows.take();
+    let row_index = args.row_index.take();
+    let rechunk = args.rechunk;
+    scan_files(
+        paths,
+        include_file_paths,
+        n_rows,
+        row_index,
+        rechunk,
+        |path| {
+            pl::LazyFrame::scan_parquet(path, args.clone())
+                .and_then(|lf| apply_schemas(lf, path, &schemas))
+        },
+    )
+    .map(RPolarsLazyFrame)
+}
+
+// first value of a statistics array, as a string so that columns of any type fit in one column
+fn first_value_as_string(array: Box<dyn polars::export::arrow::array::Array>) -> Option<String> {
+    let s = pl::Series::try_from(("", array)).ok()?;
+    let s = s.cast(&pl::DataType::String).ok()?;
+    let value = s.str().ok()?.get(0)?.to_string();
+    Some(value)
+}
+
+fn first_value_as_i64(array: Box<dyn polars::export::arrow::array::Array>) -> Option<i64> {
+    let s = pl::Series::try_from(("", array)).ok()?;
+    let s = s.cast(&pl::DataType::Int64).ok()?;
+    s.i64().ok()?.get(0)
+}
+
+#[extendr]
+pub fn parquet_metadata(path: Robj) -> RResult<List> {
+    let path = robj_to!(String, path)?;
+    let mut f = std::fs::File::open(&path)
+        .bad_val(path.clone())
+        .when("opening the parquet file")?;
+    let md = polars_parquet::read::read_metadata(&mut f)
+        .bad_val(path.clone())
+        .when("reading the parquet metadata")?;
+    let arrow_schema = polars_parquet::read::infer_schema(&md)
+        .bad_val(path)
+        .when("reading the parquet schema")?;
+
+    let mut row_group = Vec::new();
+    let mut row_group_num_rows = Vec::new();
+    let mut column = Vec::new();
+    let mut compression = Vec::new();
+    let mut compressed_size = Vec::new();
+    let mut uncompressed_size = Vec::new();
+    let mut null_count = Vec::new();
+    let mut min = Vec::new();
+    let mut max = Vec::new();
+    for (i, rg) in md.row_groups.iter().enumerate() {
+        for field in arrow_schema.fields.iter() {
+            // a nested column is stored in several column chunks
+            let chunks: Vec<_> = rg
+                .columns()
+                .iter()
+                .filter(|c| c.descriptor().path_in_schema.first() == Some(&field.name))
+                .collect();
+            let stats = polars_parquet::read::statistics::deserialize(field, rg).ok();
+            row_group.push(i as i64);
+            row_group_num_rows.push(rg.num_rows() as i64);
+            column.push(field.name.clone());
+            compression.push(chunks.first().map(|c| format!("{:?}", c.compression())));
+            compressed_size.push(chunks.iter().map(|c| c.compressed_size()).sum::<i64>());
+            uncompressed_size.push(chunks.iter().map(|c| c.uncompressed_size()).sum::<i64>());
+            match stats {
+                Some(st) => {
+                    null_count.push(first_value_as_i64(st.null_count));
+                    min.push(first_value_as_string(st.min_value));
+                    max.push(first_value_as_string(st.max_value));
+                }
+                None => {
+                    null_count.push(None);
+                    min.push(None);
+                    max.push(None);
+                }
+            }
+        }
+    }
+    let columns = pl::DataFrame::new(vec![
+        pl::Series::new("row_group", row_group),
+        pl::Series::new("row_group_num_rows", row_group_num_rows),
+        pl::Series::new("column", column),
+        pl::Series::new("compression", compression),
+        pl::Series::new("compressed_size", compressed_size),
+        pl::Series::new("uncompressed_size", uncompressed_size),
+        pl::Series::new("null_count", null_count),
+        pl::Series::new("min", min),
+        pl::Series::new("max", max),
+    ])
+    .map_err(polars_to_rpolars_err)?;
+
+    let schema = pl::Schema::from_iter(arrow_schema.fields.iter());
+    let schema = Pairlist::from_pairs(
+        schema
+            .iter()
+            .map(|(name, dtype)| (name.as_str(), RPolarsDataType(dtype.clone()))),
+    );
+    let (keys, values): (Vec<_>, Vec<_>) = md
+        .key_value_metadata
+        .iter()
+        .flatten()
+        .map(|kv| (kv.key.clone(), kv.value.clone().unwrap_or_default()))
+        .unzip();
+    let key_value_metadata = List::from_names_and_values(keys, values)?;
+
+    Ok(list!(
+        num_rows = md.num_rows as f64,
+        created_by = md.created_by.clone().unwrap_or_default(),
+        schema = schema,
+        key_value_metadata = key_value_metadata,
+        row_groups = RPolarsDataFrame(columns)
+    ))
 }
 
 extendr_module! {
     mod read_parquet;
     fn new_from_parquet;
+    fn parquet_metadata;
 }
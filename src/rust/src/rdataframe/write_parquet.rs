@@ -0,0 +1,137 @@
+//write parquet files
+
+use super::write_partitioned::BatchWriter;
+use polars::prelude::{self as pl, DataFrame, PolarsResult};
+use polars_parquet::write::KeyValue;
+use std::io::Write;
+
+// number of rows of a row group if the row group size is not set
+const DEFAULT_ROW_GROUP_SIZE: usize = 512 * 512;
+
+pub fn parquet_writer<W: Write>(
+    writer: W,
+    options: &pl::ParquetWriteOptions,
+) -> pl::ParquetWriter<W> {
+    pl::ParquetWriter::new(writer)
+        .with_compression(options.compression)
+        .with_statistics(options.statistics)
+        .with_row_group_size(options.row_group_size)
+        .with_data_page_size(options.data_pagesize_limit)
+        .set_parallel(true)
+}
+
+// Writes a parquet file batch by batch. The batches are buffered until they fill a row group,
+// and ParquetWriter cannot write custom key-value metadata, so the footer is written here.
+pub struct ParquetFileWriter<W: Write> {
+    writer: polars::io::parquet::BatchedWriter<W>,
+    buffer: Option<DataFrame>,
+    row_group_size: usize,
+    metadata: Vec<KeyValue>,
+}
+
+impl<W: Write> ParquetFileWriter<W> {
+    pub fn new(
+        writer: W,
+        schema: &pl::Schema,
+        options: &pl::ParquetWriteOptions,
+        metadata: &[(String, String)],
+    ) -> PolarsResult<Self> {
+        Ok(Self {
+            writer: parquet_writer(writer, options).batched(schema)?,
+            buffer: None,
+            row_group_size: options
+                .row_group_size
+                .unwrap_or(DEFAULT_ROW_GROUP_SIZE)
+                .max(1),
+            metadata: metadata
+                .iter()
+                .map(|(key, value)| KeyValue {
+                    key: key.clone(),
+                    value: Some(value.clone()),
+                })
+                .collect(),
+        })
+    }
+
+    // Write the buffered rows as full row groups, and if `all`, the remaining rows too.
+    fn flush(&mut self, all: bool) -> PolarsResult<()> {
+        let Some(mut buffer) = self.buffer.take() else {
+            return Ok(());
+        };
+        let size = self.row_group_size;
+        let n_groups = if all {
+            buffer.height().div_ceil(size)
+        } else {
+            buffer.height() / size
+        };
+        if n_groups > 0 {
+            buffer.as_single_chunk_par();
+        }
+        for i in 0..n_groups {
+            self.writer
+                .write_batch(&buffer.slice((i * size) as i64, size))?;
+        }
+        if n_groups * size < buffer.height() {
+            self.buffer = Some(buffer.slice((n_groups * size) as i64, usize::MAX));
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> BatchWriter for ParquetFileWriter<W> {
+    fn write(&mut self, df: &DataFrame) -> PolarsResult<()> {
+        match &mut self.buffer {
+            Some(buffer) => {
+                buffer.vstack_mut(df)?;
+            }
+            None => self.buffer = Some(df.clone()),
+        }
+        self.flush(false)
+    }
+
+    fn buffered_rows(&self) -> usize {
+        self.buffer.as_ref().map_or(0, DataFrame::height)
+    }
+
+    fn flush_buffer(&mut self) -> PolarsResult<()> {
+        self.flush(true)
+    }
+
+    fn finish(mut self) -> PolarsResult<u64> {
+        self.flush(true)?;
+        let metadata = (!self.metadata.is_empty()).then_some(self.metadata);
+        let size = self.writer.get_writer().lock().unwrap().end(metadata)?;
+        Ok(size)
+    }
+}
+
+// Write `batches` of the given schema as one parquet file.
+pub fn write_parquet_batches<I, W>(
+    batches: I,
+    writer: W,
+    schema: &pl::Schema,
+    options: &pl::ParquetWriteOptions,
+    metadata: &[(String, String)],
+) -> PolarsResult<u64>
+where
+    I: IntoIterator<Item = PolarsResult<DataFrame>>,
+    W: Write,
+{
+    let mut file = ParquetFileWriter::new(writer, schema, options, metadata)?;
+    for batch in batches {
+        file.write(&batch?)?;
+    }
+    file.finish()
+}
+
+pub fn write_parquet_file<W: Write>(
+    df: &mut DataFrame,
+    writer: W,
+    options: &pl::ParquetWriteOptions,
+    metadata: &[(String, String)],
+) -> PolarsResult<u64> {
+    if metadata.is_empty() {
+        return parquet_writer(writer, options).finish(df);
+    }
+    write_parquet_batches([Ok(df.clone())], writer, &df.schema(), options, metadata)
+}
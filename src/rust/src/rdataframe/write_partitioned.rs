@@ -0,0 +1,200 @@
+//write hive partitioned datasets
+
+use polars::prelude::{self as pl, DataFrame, PolarsResult};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+// Value of a partition key in a `key=value` directory name. Follows the conventions of Hive
+// and Spark so that the datasets can be read back by other tools: nulls are written as
+// __HIVE_DEFAULT_PARTITION__ and characters with a special meaning in paths are %-encoded.
+fn partition_value(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "__HIVE_DEFAULT_PARTITION__".into();
+    };
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\u{00}'..='\u{1F}'
+            | '"'
+            | '#'
+            | '%'
+            | '\''
+            | '*'
+            | '/'
+            | ':'
+            | '='
+            | '?'
+            | '\\'
+            | '\u{7F}'
+            | '{'
+            | '['
+            | ']'
+            | '^' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// A file of a partitioned dataset that is written batch by batch.
+pub trait BatchWriter {
+    fn write(&mut self, df: &DataFrame) -> PolarsResult<()>;
+    // Number of rows written but still held in memory, e.g. to fill a row group.
+    fn buffered_rows(&self) -> usize;
+    // Write out the rows held in memory.
+    fn flush_buffer(&mut self) -> PolarsResult<()>;
+    // Finish the file, and return its size in bytes.
+    fn finish(self) -> PolarsResult<u64>;
+}
+
+// at most this many files are open at once while writing a partitioned dataset
+const MAX_OPEN_FILES: usize = 512;
+// at most this many rows are held in memory by all open files together
+const MAX_BUFFERED_ROWS: usize = 1024 * 1024;
+
+struct Partition<W> {
+    file: Option<W>,
+    n_files: usize,
+    rows_in_file: usize,
+    last_write: u64,
+}
+
+impl<W> Default for Partition<W> {
+    fn default() -> Self {
+        Self {
+            file: None,
+            n_files: 0,
+            rows_in_file: 0,
+            last_write: 0,
+        }
+    }
+}
+
+fn finish_least_recent<W: BatchWriter>(
+    partitions: &mut HashMap<PathBuf, Partition<W>>,
+) -> PolarsResult<u64> {
+    partitions
+        .values_mut()
+        .filter(|p| p.file.is_some())
+        .min_by_key(|p| p.last_write)
+        .and_then(|p| p.file.take())
+        .map_or(Ok(0), BatchWriter::finish)
+}
+
+// Flush the files that hold the most rows in memory until all of them together hold at most
+// MAX_BUFFERED_ROWS rows. The flushed files get smaller row groups, but the memory used does
+// not grow with the number of partitions.
+fn flush_largest<W: BatchWriter>(
+    partitions: &mut HashMap<PathBuf, Partition<W>>,
+) -> PolarsResult<()> {
+    let mut buffered: usize = partitions
+        .values()
+        .filter_map(|p| p.file.as_ref())
+        .map(BatchWriter::buffered_rows)
+        .sum();
+    while buffered > MAX_BUFFERED_ROWS {
+        let Some(file) = partitions
+            .values_mut()
+            .filter_map(|p| p.file.as_mut())
+            .max_by_key(|file| file.buffered_rows())
+        else {
+            break;
+        };
+        buffered -= file.buffered_rows();
+        file.flush_buffer()?;
+    }
+    Ok(())
+}
+
+// A partitioned dataset is only written into a new or empty directory, so that no files of a
+// previous dataset end up mixed into it.
+pub fn check_empty_dir(root: &Path) -> PolarsResult<()> {
+    if std::fs::read_dir(root).is_ok_and(|mut entries| entries.next().is_some()) {
+        pl::polars_bail!(
+            ComputeError: "the directory '{}' is not empty, remove it first to write a partitioned dataset into it",
+            root.display()
+        );
+    }
+    Ok(())
+}
+
+// Write `batches` to a directory tree `root/key1=value1/key2=value2/data-0000.<extension>`. The
+// partition columns are only encoded in the paths, not in the files. The rows of a partition
+// are appended to its current file, in the order of the batches, until that file holds
+// `max_rows_per_file` rows and the next one is started. If more than MAX_OPEN_FILES files would
+// be open, the least recently written one is finished and its partition continues in a new
+// file. The files hold at most MAX_BUFFERED_ROWS rows in memory together, see flush_largest().
+// Returns the sum of the file sizes.
+pub fn write_partitioned<I, W, F>(
+    batches: I,
+    root: &Path,
+    by: &[String],
+    max_rows_per_file: Option<usize>,
+    extension: &str,
+    create: F,
+) -> PolarsResult<u64>
+where
+    I: IntoIterator<Item = PolarsResult<DataFrame>>,
+    W: BatchWriter,
+    F: Fn(File, &pl::Schema) -> PolarsResult<W>,
+{
+    check_empty_dir(root)?;
+    let rows_per_file = max_rows_per_file.unwrap_or(usize::MAX).max(1);
+    let mut partitions: HashMap<PathBuf, Partition<W>> = HashMap::new();
+    let mut n_open = 0;
+    let mut n_writes = 0;
+    let mut written = 0;
+
+    for batch in batches {
+        for partition in batch?.partition_by_stable(by.to_vec(), true)? {
+            let mut dir = root.to_path_buf();
+            for key in by {
+                let value = partition.column(key)?.cast(&pl::DataType::String)?;
+                dir.push(format!("{key}={}", partition_value(value.str()?.get(0))));
+            }
+
+            let mut rows = partition.drop_many(by);
+            while rows.height() > 0 {
+                let is_open = partitions.get(&dir).is_some_and(|p| p.file.is_some());
+                if !is_open && n_open == MAX_OPEN_FILES {
+                    written += finish_least_recent(&mut partitions)?;
+                    n_open -= 1;
+                }
+
+                let p = partitions.entry(dir.clone()).or_default();
+                n_writes += 1;
+                p.last_write = n_writes;
+                if p.file.is_none() {
+                    std::fs::create_dir_all(&dir)?;
+                    let path = dir.join(format!("data-{:04}.{extension}", p.n_files));
+                    p.file = Some(create(File::create(path)?, &rows.schema())?);
+                    p.n_files += 1;
+                    p.rows_in_file = 0;
+                    n_open += 1;
+                }
+                let n = rows.height().min(rows_per_file - p.rows_in_file);
+                if let Some(file) = &mut p.file {
+                    file.write(&rows.slice(0, n))?;
+                }
+                p.rows_in_file += n;
+                rows = rows.slice(n as i64, usize::MAX);
+
+                if p.rows_in_file == rows_per_file {
+                    if let Some(file) = p.file.take() {
+                        written += file.finish()?;
+                    }
+                    n_open -= 1;
+                }
+            }
+        }
+        flush_largest(&mut partitions)?;
+    }
+
+    for p in partitions.into_values() {
+        if let Some(file) = p.file {
+            written += file.finish()?;
+        }
+    }
+    Ok(written)
+}
@@ -12,6 +12,7 @@ use std::result::Result;
 #[derive(Debug, Clone, PartialEq)]
 pub struct RPolarsRField(pub pl::Field);
 use pl::UniqueKeepStrategy;
+use polars::io::is_cloud_url;
 use polars::prelude::AsofStrategy;
 
 use crate::utils::robj_to_rchoice;
@@ -92,8 +93,22 @@ impl RPolarsDataType {
             .map(|dt| RPolarsDataType(pl::DataType::Datetime(dt, null_to_opt(tz))))
     }
 
-    pub fn new_duration() -> RPolarsDataType {
-        todo!("duration not implemented")
+    pub fn new_duration(tu: Robj) -> RResult<RPolarsDataType> {
+        robj_to!(timeunit, tu).map(|tu| RPolarsDataType(pl::DataType::Duration(tu)))
+    }
+
+    pub fn new_decimal(precision: Robj, scale: Robj) -> RResult<RPolarsDataType> {
+        Ok(RPolarsDataType(pl::DataType::Decimal(
+            robj_to!(Option, usize, precision)?,
+            Some(robj_to!(usize, scale)?),
+        )))
+    }
+
+    pub fn new_enum(categories: Robj) -> RResult<RPolarsDataType> {
+        let categories = robj_to!(Vec, String, categories)?;
+        new_enum_datatype(categories.iter().map(|x| x.as_str()))
+            .map_err(polars_to_rpolars_err)
+            .map(RPolarsDataType)
     }
 
     pub fn new_list(inner: &RPolarsDataType) -> RPolarsDataType {
@@ -187,6 +202,14 @@ impl RPolarsDataType {
                     extendr_api::NULL.into_robj()
                 },
             ),
+            pl::DataType::Duration(tu) => list!(tu = tu.to_string().into_robj()),
+            pl::DataType::Decimal(precision, scale) => list!(
+                precision = precision.map(|x| x as f64).into_robj(),
+                scale = scale.map(|x| x as f64).into_robj(),
+            ),
+            pl::DataType::Enum(Some(rev_map), _) => {
+                list!(categories = enum_categories(&rev_map).into_robj())
+            }
             pl::DataType::List(inner) => {
                 list!(RPolarsDataType(*inner).into_robj())
             }
@@ -243,6 +266,13 @@ impl RPolarsDataTypeVector {
         let v: Vec<_> = self.0.iter().map(|(_, dt)| dt.clone()).collect();
         v
     }
+
+    pub fn dtv_to_schema(&self) -> pl::Schema {
+        self.0
+            .iter()
+            .map(|(name, dt)| pl::Field::new(name.as_deref().unwrap_or(""), dt.clone()))
+            .collect()
+    }
 }
 
 pub fn robj_to_asof_strategy(robj: Robj) -> RResult<AsofStrategy> {
@@ -401,6 +431,38 @@ pub fn time_unit_converson(tu: pl::TimeUnit) -> i64 {
     tu_i64
 }
 
+// an Enum has a local mapping whose categories are in the given order, so that the physical
+// value of each category is its position
+pub fn new_enum_datatype<'a>(
+    categories: impl Iterator<Item = &'a str>,
+) -> pl::PolarsResult<pl::DataType> {
+    use pl::NewChunkedArray;
+    let categories: Vec<&str> = categories.collect();
+    let mut seen = std::collections::HashSet::with_capacity(categories.len());
+    if let Some(dup) = categories.iter().find(|x| !seen.insert(**x)) {
+        pl::polars_bail!(ComputeError: "the categories of an Enum must be unique, found '{}' twice", dup);
+    }
+    let ca = pl::StringChunked::from_iter_values("", categories.into_iter()).rechunk();
+    let categories = ca
+        .downcast_iter()
+        .next()
+        .expect("a rechunked array has one chunk")
+        .clone();
+    let rev_map = pl::RevMapping::build_local(categories);
+    Ok(pl::DataType::Enum(
+        Some(std::sync::Arc::new(rev_map)),
+        Default::default(),
+    ))
+}
+
+pub fn enum_categories(rev_map: &pl::RevMapping) -> Vec<String> {
+    rev_map
+        .get_categories()
+        .values_iter()
+        .map(|x| x.to_string())
+        .collect()
+}
+
 pub fn new_categorical_ordering(s: &str) -> Result<pl::CategoricalOrdering, String> {
     use pl::CategoricalOrdering as CO;
     match s {
@@ -446,13 +508,88 @@ pub fn new_ipc_compression(compression_method: Robj) -> RResult<Option<pl::IpcCo
     use pl::IpcCompression::*;
     robj_to!(Option, String, compression_method)?
         .map(|cm| match cm.as_str() {
-            "lz4" => Ok(LZ4),
-            "zstd" => Ok(ZSTD),
+            "uncompressed" => Ok(None),
+            "lz4" => Ok(Some(LZ4)),
+            "zstd" => Ok(Some(ZSTD)),
             m => rerr()
                 .bad_val(m)
-                .misvalued("should be one of ['lz4', 'zstd']"),
+                .misvalued("should be one of ['uncompressed', 'lz4', 'zstd']"),
         })
         .transpose()
+        .map(Option::flatten)
+}
+
+pub fn new_avro_compression(
+    compression_method: Robj,
+) -> RResult<Option<polars::io::avro::AvroCompression>> {
+    use polars::io::avro::AvroCompression::*;
+    robj_to!(Option, String, compression_method)?
+        .map(|cm| match cm.as_str() {
+            "uncompressed" => Ok(None),
+            "deflate" => Ok(Some(Deflate)),
+            "snappy" => Ok(Some(Snappy)),
+            m => rerr()
+                .bad_val(m)
+                .misvalued("should be one of ['uncompressed', 'deflate', 'snappy']"),
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+// The URL scheme of `path` if polars reads it from an object store or a web server, as
+// decided by polars itself. Other paths are local.
+fn cloud_scheme(path: &str) -> Option<String> {
+    if !is_cloud_url(path) {
+        return None;
+    }
+    path.split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase())
+}
+
+// The cloud options of a scan are built for the first path, so the other paths must be
+// local as well or use the same scheme.
+pub fn check_same_scheme(paths: &[String]) -> RResult<()> {
+    let Some(first) = paths.first() else {
+        return Ok(());
+    };
+    let first_scheme = cloud_scheme(first);
+    match paths.iter().find(|p| cloud_scheme(p) != first_scheme) {
+        Some(other) => rerr()
+            .bad_val(other)
+            .plain(format!(
+                "all paths must be local or use the same URL scheme as the first path '{first}'"
+            ))
+            .bad_arg("path"),
+        None => Ok(()),
+    }
+}
+
+// Options of object stores (s3://, gs://, az://, ...) are passed from R as a named list of
+// strings, e.g. list(aws_region = "us-east-1", aws_endpoint_url = "http://localhost:9000").
+// Local paths get no cloud options at all.
+pub fn new_cloud_options(
+    path: &str,
+    storage_options: Robj,
+    retries: Robj,
+) -> RResult<Option<polars::io::cloud::CloudOptions>> {
+    let max_retries = robj_to!(usize, retries)?;
+    let config = crate::utils::robj_to_named_strings(storage_options).bad_arg("storage_options")?;
+
+    if !is_cloud_url(path) {
+        return if config.is_empty() {
+            Ok(None)
+        } else {
+            rerr()
+                .bad_val(path)
+                .plain("`storage_options` can only be used with cloud URLs, e.g. s3://")
+        };
+    }
+
+    let mut cloud_options = polars::io::cloud::CloudOptions::from_untyped_config(path, config)
+        .map_err(polars_to_rpolars_err)
+        .bad_arg("storage_options")?;
+    cloud_options.max_retries = max_retries;
+    Ok(Some(cloud_options))
 }
 
 pub fn new_rolling_cov_options(
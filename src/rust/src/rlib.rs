@@ -1,3 +1,4 @@
+use crate::lazy::dataframe::RPolarsLazyFrame;
 use crate::lazy::dsl::{RPolarsExpr, RPolarsProtoExprArray};
 use crate::rdataframe::RPolarsDataFrame;
 use crate::robj_to;
@@ -141,9 +142,7 @@ fn new_arrow_stream() -> Robj {
     crate::arrow_interop::to_rust::new_arrow_stream_internal()
 }
 use crate::rpolarserr::*;
-#[extendr]
-fn arrow_stream_to_df(robj_str: Robj) -> RResult<Robj> {
-    let s = crate::arrow_interop::to_rust::arrow_stream_to_series_internal(robj_str)?;
+fn struct_series_to_df(s: pl::Series) -> RResult<Robj> {
     let ca = s
         .struct_()
         .map_err(polars_to_rpolars_err)
@@ -153,6 +152,24 @@ fn arrow_stream_to_df(robj_str: Robj) -> RResult<Robj> {
     Ok(RPolarsDataFrame(df).into_robj())
 }
 
+#[extendr]
+fn arrow_stream_to_df(robj_str: Robj) -> RResult<Robj> {
+    let s = crate::arrow_interop::to_rust::arrow_stream_to_series_internal(robj_str)?;
+    struct_series_to_df(s)
+}
+
+#[extendr]
+fn arrow_stream_ptr_to_df(stream_ptr: Robj) -> RResult<Robj> {
+    let s = crate::arrow_interop::to_rust::arrow_stream_ptr_to_series_internal(stream_ptr)?;
+    struct_series_to_df(s)
+}
+
+#[extendr]
+fn arrow_stream_to_lf(robj_str: Robj) -> RResult<RPolarsLazyFrame> {
+    crate::arrow_interop::to_rust::arrow_stream_to_lazyframe_internal(robj_str)
+        .map(RPolarsLazyFrame)
+}
+
 #[extendr]
 fn arrow_stream_to_series(robj_str: Robj) -> RResult<Robj> {
     let s = crate::arrow_interop::to_rust::arrow_stream_to_series_internal(robj_str)?;
@@ -319,6 +336,8 @@ extendr_module! {
     // arrow conversions
     fn new_arrow_stream;
     fn arrow_stream_to_df;
+    fn arrow_stream_ptr_to_df;
+    fn arrow_stream_to_lf;
     fn arrow_stream_to_series;
     fn export_df_to_arrow_stream;
 
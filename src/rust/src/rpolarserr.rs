@@ -299,6 +299,10 @@ pub fn extendr_to_rpolars_err(extendr_err: extendr_api::Error) -> RPolarsErr {
     RPolarsErr::new_from_ctx(Rctx::Extendr(rdbg(extendr_err)))
 }
 
+pub fn serde_json_to_rpolars_err(serde_err: serde_json::Error) -> RPolarsErr {
+    RPolarsErr::new_from_ctx(Rctx::Plain(serde_err.to_string()))
+}
+
 pub fn rpolars_to_polars_err(rpolars_err: RPolarsErr) -> polars::error::PolarsError {
     polars::prelude::PolarsError::ComputeError(
         serde_json::to_string(&rpolars_err)
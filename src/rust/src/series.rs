@@ -6,7 +6,7 @@
 use crate::apply_input;
 use crate::apply_output;
 use crate::conversion_r_to_s::robjname2series;
-use crate::conversion_s_to_r::pl_series_to_list;
+use crate::conversion_s_to_r::{pl_series_to_list, StructConversion};
 use crate::handle_type;
 use crate::lazy::dsl::RPolarsExpr;
 use crate::make_r_na_fun;
@@ -78,9 +78,20 @@ impl RPolarsSeries {
         panic!("somebody panicked on purpose");
     }
 
-    pub fn to_r(&self, int64_conversion: &str) -> std::result::Result<Robj, String> {
-        pl_series_to_list(&self.0, true, int64_conversion)
-            .map_err(|err| format!("in to_r: {:?}", err))
+    pub fn to_r(
+        &self,
+        int64_conversion: &str,
+        decimal_conversion: &str,
+        datetime_conversion: &str,
+    ) -> std::result::Result<Robj, String> {
+        pl_series_to_list(
+            &self.0,
+            StructConversion::Tag,
+            int64_conversion,
+            decimal_conversion,
+            datetime_conversion,
+        )
+        .map_err(|err| format!("in to_r: {:?}", err))
     }
     //any mut method exposed in R suffixed _mut
     pub fn rename_mut(&mut self, name: &str) {
@@ -439,27 +450,27 @@ impl RPolarsSeries {
     }
 
     pub fn mean(&self) -> Result<Robj, String> {
-        RPolarsSeries(self.0.mean_as_series()).to_r("double")
+        RPolarsSeries(self.0.mean_as_series()).to_r("double", "double", "POSIXct")
     }
 
     pub fn median(&self) -> Result<Robj, String> {
         let s = self.0.median_as_series().map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn min(&self) -> Result<Robj, String> {
         let s = self.0.min_as_series().map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn max(&self) -> Result<Robj, String> {
         let s = self.0.max_as_series().map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn sum(&self) -> Result<Robj, String> {
         let s = self.0.sum_as_series().map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn std(&self, ddof: Robj) -> Result<Robj, String> {
@@ -467,7 +478,7 @@ impl RPolarsSeries {
             .0
             .std_as_series(robj_to!(u8, ddof)?)
             .map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn var(&self, ddof: Robj) -> Result<Robj, String> {
@@ -475,7 +486,7 @@ impl RPolarsSeries {
             .0
             .var_as_series(robj_to!(u8, ddof)?)
             .map_err(polars_to_rpolars_err)?;
-        RPolarsSeries(s).to_r("double")
+        RPolarsSeries(s).to_r("double", "double", "POSIXct")
     }
 
     pub fn print(&self) {
@@ -496,6 +507,21 @@ impl RPolarsSeries {
         };
     }
 
+    pub fn to_raw_ipc(&self, compression: Robj) -> RResult<Robj> {
+        crate::rbackground::serialize_series_with_compression(
+            self.0.clone(),
+            crate::rdatatype::new_ipc_compression(compression)?,
+        )
+        .map(|bits| r!(Raw::from_bytes(&bits)))
+    }
+
+    pub fn from_raw_ipc(bits: Robj) -> RResult<RPolarsSeries> {
+        let bits = robj_to!(Raw, bits)?;
+        crate::rbackground::deserialize_series(&bits)
+            .when("deserializing a Series from raw IPC bytes")
+            .map(RPolarsSeries)
+    }
+
     pub fn from_arrow_array_stream_str(name: Robj, robj_str: Robj) -> RResult<Robj> {
         let name = robj_to!(str, name)?;
         let s = crate::arrow_interop::to_rust::arrow_stream_to_series_internal(robj_str)?
@@ -503,35 +529,11 @@ impl RPolarsSeries {
         Ok(RPolarsSeries(s).into_robj())
     }
 
-    pub fn from_arrow_array_robj(name: Robj, array: Robj) -> Result<Self, String> {
+    pub fn from_arrow_array_stream_ptr(name: Robj, stream_ptr: Robj) -> RResult<Robj> {
         let name = robj_to!(str, name)?;
-        let arr = crate::arrow_interop::to_rust::arrow_array_to_rust(array)?;
-
-        match arr.data_type() {
-            ArrowDataType::LargeList(_) => {
-                let array = arr.as_any().downcast_ref::<pl::LargeListArray>().unwrap();
-
-                let mut previous = 0;
-                let mut fast_explode = true;
-                for &o in array.offsets().as_slice()[1..].iter() {
-                    if o == previous {
-                        fast_explode = false;
-                        break;
-                    }
-                    previous = o;
-                }
-                let mut out = unsafe { ListChunked::from_chunks(name, vec![arr]) };
-                if fast_explode {
-                    out.set_fast_explode()
-                }
-                Ok(out.into_series().into())
-            }
-            _ => {
-                let series_res: Result<pl::Series, pl::PolarsError> =
-                    std::convert::TryFrom::try_from((name, arr));
-                Ok(series_res.map_err(|err| err.to_string())?.into())
-            }
-        }
+        let s = crate::arrow_interop::to_rust::arrow_stream_ptr_to_series_internal(stream_ptr)?
+            .with_name(name);
+        Ok(RPolarsSeries(s).into_robj())
     }
 }
 
@@ -439,6 +439,147 @@ pub fn reinterpret(s: &pl::Series, signed: bool) -> pl::PolarsResult<pl::Series>
     }
 }
 
+// For an array of dimensions `dims`, the position in the column-major layout of R of
+// each element of the row-major layout of polars, where the first dimension is the row.
+pub fn row_major_to_col_major(dims: &[usize]) -> Vec<pl::IdxSize> {
+    let mut col_strides = Vec::with_capacity(dims.len());
+    let mut stride = 1;
+    for dim in dims {
+        col_strides.push(stride);
+        stride *= dim;
+    }
+    (0..stride)
+        .map(|mut row_major| {
+            let mut col_major = 0;
+            for (dim, col_stride) in dims.iter().zip(col_strides.iter()).rev() {
+                col_major += (row_major % dim) * col_stride;
+                row_major /= dim;
+            }
+            col_major as pl::IdxSize
+        })
+        .collect()
+}
+
+// A path for a temporary file next to `path`, the file is removed when this is dropped. The
+// name contains the process id and a counter so that concurrent writes do not collide.
+pub struct TempPath(std::path::PathBuf);
+
+impl TempPath {
+    pub fn next_to(path: &std::path::Path) -> Self {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let name = path
+            .file_name()
+            .map_or("polars".into(), |name| name.to_string_lossy());
+        Self(path.with_file_name(format!(".{name}.{}-{n}.tmp", std::process::id())))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+// Expand glob patterns of local paths, e.g. "data/*.parquet" or "data/**/*.ipc".
+// Cloud URLs are kept as is for the scanner to expand, and so are patterns without any
+// match so that the scanner reports the missing file.
+pub fn expand_paths(paths: Vec<String>) -> RResult<Vec<String>> {
+    let mut expanded = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !is_glob(&path) || polars::io::is_cloud_url(&path) {
+            expanded.push(path);
+            continue;
+        }
+        let mut matched = glob::glob(&path)
+            .bad_val(path.clone())
+            .when("expanding the glob pattern")?
+            .map(|entry| entry.map(|p| p.to_string_lossy().to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .bad_val(path.clone())
+            .when("expanding the glob pattern")?;
+        if matched.is_empty() {
+            expanded.push(path);
+        } else {
+            matched.sort();
+            expanded.append(&mut matched);
+        }
+    }
+    Ok(expanded)
+}
+
+// The files matched by a glob pattern of a cloud URL are only known to the scanner, so options
+// that depend on the path of each file, named by `used_with`, cannot be used with such patterns.
+pub fn check_no_cloud_glob(paths: &[String], used_with: &str) -> RResult<()> {
+    match paths
+        .iter()
+        .find(|p| is_glob(p) && polars::io::is_cloud_url(p))
+    {
+        Some(url) => rerr()
+            .bad_val(url)
+            .plain(format!(
+                "glob patterns of cloud URLs cannot be used with {used_with}"
+            ))
+            .hint("list the files matching the pattern instead"),
+        None => Ok(()),
+    }
+}
+
+// Scan several files as one LazyFrame. `scan` reads a single file, it must neither add a
+// row index nor limit the number of rows as both are applied to the concatenated frame.
+// If `include_file_paths` is set, a column of that name holds the source path of each row.
+pub fn scan_files<F>(
+    paths: Vec<String>,
+    include_file_paths: Option<String>,
+    n_rows: Option<usize>,
+    row_index: Option<polars::io::RowIndex>,
+    rechunk: bool,
+    scan: F,
+) -> RResult<pl::LazyFrame>
+where
+    F: Fn(&str) -> pl::PolarsResult<pl::LazyFrame>,
+{
+    if include_file_paths.is_some() {
+        check_no_cloud_glob(&paths, "`include_file_paths`")?;
+    }
+    let lfs = expand_paths(paths)?
+        .iter()
+        .map(|path| {
+            let lf = scan(path)?;
+            Ok(match &include_file_paths {
+                Some(name) => lf.with_column(pl::lit(path.as_str()).alias(name)),
+                None => lf,
+            })
+        })
+        .collect::<pl::PolarsResult<Vec<_>>>()
+        .map_err(polars_to_rpolars_err)?;
+
+    let mut lf = polars::lazy::dsl::concat(
+        lfs,
+        pl::UnionArgs {
+            parallel: true,
+            rechunk,
+            to_supertypes: false,
+        },
+    )
+    .map_err(polars_to_rpolars_err)?;
+    if let Some(ri) = row_index {
+        lf = lf.with_row_index(ri.name.as_str(), Some(ri.offset));
+    }
+    if let Some(n) = n_rows {
+        lf = lf.limit(n as pl::IdxSize);
+    }
+    Ok(lf)
+}
+
 pub fn inner_unpack_r_result_list(robj: extendr_api::Robj) -> Result<Robj, Robj> {
     use extendr_api::*;
     if robj.inherits("extendr_result") {
@@ -691,6 +832,33 @@ pub fn robj_to_bool(robj: extendr_api::Robj) -> RResult<bool> {
         .mistyped(tn::<bool>())
 }
 
+// named list or named character vector of strings, e.g. list(key = "value"), NULL is empty
+pub fn robj_to_named_strings(robj: extendr_api::Robj) -> RResult<Vec<(String, String)>> {
+    let robj = unpack_r_result_list(robj)?;
+    if robj.is_null() {
+        return Ok(Vec::new());
+    }
+    let robj = if robj.is_list() {
+        robj
+    } else {
+        extendr_api::call!("as.list", robj.clone())
+            .map_err(|_| RPolarsErr::new())
+            .bad_robj(&robj)
+            .mistyped("named list")?
+    };
+    robj.as_list()
+        .expect("is a list")
+        .iter()
+        .map(|(key, value)| {
+            if key.is_empty() || key == "NA" {
+                return rerr().plain("all elements must be named");
+            }
+            let value = robj_to_string(value).hint(format!("element [{key}]"))?;
+            Ok((key.to_string(), value))
+        })
+        .collect()
+}
+
 pub fn robj_to_binary_vec(robj: extendr_api::Robj) -> RResult<Vec<u8>> {
     let robj = unpack_r_result_list(robj)?;
     let binary_vec: Vec<u8> = robj